@@ -0,0 +1,184 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Mul};
+
+/// Arbitrary-precision unsigned integer stored as little-endian base 2^32 limbs,
+/// always without trailing zero limbs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+
+    pub fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    pub fn one() -> Self {
+        Self { limbs: vec![1] }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 { return None }
+        Some(self.limbs.iter().rev().fold(0, |acc, &limb| (acc << 32) | limb as u128))
+    }
+
+    pub fn mul_small(&self, factor: u32) -> Self {
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = 0u64;
+        for &limb in &self.limbs {
+            let product = limb as u64 * factor as u64 + carry;
+            limbs.push(product as u32);
+            carry = product >> 32;
+        }
+        limbs.push(carry as u32);
+        Self::normalized(limbs)
+    }
+
+    pub fn add_small(&self, term: u32) -> Self {
+        self + &BigUint::from(term as u64)
+    }
+
+    /// Divides in place by `divisor` and returns the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = (remainder << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        self.trim();
+        remainder as u32
+    }
+
+    fn normalized(limbs: Vec<u32>) -> Self {
+        let mut n = Self { limbs };
+        n.trim();
+        n
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) { self.limbs.pop(); }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        Self::normalized(vec![n as u32, (n >> 32) as u32])
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        Self::normalized((0..4).map(|i| (n >> (32 * i)) as u32).collect())
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() { (self, other) } else { (other, self) };
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = 0u64;
+        for (index, &limb) in long.limbs.iter().enumerate() {
+            let sum = limb as u64 + *short.limbs.get(index).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        BigUint::normalized(limbs)
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() { return BigUint::zero() }
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let current = limbs[i + j] as u64 + a as u64 * b as u64 + carry;
+                limbs[i + j] = current as u32;
+                carry = current >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::normalized(limbs)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::str::FromStr for BigUint {
+
+    type Err = String;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        if data.is_empty() { return Err(String::from("Empty number")) }
+        let mut n = BigUint::zero();
+        for c in data.chars() {
+            let digit = c.to_digit(10).ok_or(String::from("Invalid digit"))?;
+            n = n.mul_small(10).add_small(digit);
+        }
+        Ok(n)
+    }
+}
+
+impl std::fmt::Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_zero() { return write!(f, "0") }
+        let mut n = self.clone();
+        let mut chunks = Vec::new();
+        while !n.is_zero() { chunks.push(n.div_rem_small(1_000_000_000)); }
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap_or(0))?;
+        for chunk in chunks { write!(f, "{:09}", chunk)?; }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn add_and_multiply() {
+        let a = BigUint::from(u128::MAX);
+        let b = BigUint::from(u64::MAX);
+        let sum = &a + &b;
+        assert_eq!(sum.to_string(), "340282366920938463481821351505477763070");
+        let product = &a * &b;
+        assert_eq!(product.to_string(), "6277101735386680763495507056286727952620534092958556749825");
+        assert_eq!(product.to_u128(), None);
+        assert_eq!((&BigUint::from(7u64) * &BigUint::from(6u64)).to_u128(), Some(42));
+    }
+
+    #[test]
+    fn parse_compare_and_display() {
+        let n: BigUint = "123456789012345678901234567890123456789012".parse().expect("Invalid number");
+        assert_eq!(n.to_string(), "123456789012345678901234567890123456789012");
+        assert!(n > BigUint::from(u128::MAX));
+        assert!(BigUint::from(1u64) < BigUint::from(2u64));
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert!("12a".parse::<BigUint>().is_err());
+    }
+}
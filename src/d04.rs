@@ -1,37 +1,36 @@
 use std::io::BufRead;
 
-pub struct Board(Vec<Vec<Option<usize>>>);
+use crate::grid::Grid;
+
+pub struct Board(Grid<Option<usize>>);
 
 impl Board {
 
-    pub fn new(data: &Vec<Vec<usize>>) -> Self {
+    pub fn new(data: &Vec<Vec<usize>>) -> Result<Self, String> {
         let data = data.iter().map(|row| { 
             row.iter().map(|&n| Some(n)).collect() }
         ).collect();
-        Self(data)
+        Ok(Self(Grid::from_rows(data)?))
     }
     pub fn sum_unmarked(&self) -> usize {
         self.0.iter()
-            .flat_map(|xs| xs.iter())
-            .fold(0, |acc, x| { if let Some(x) = x { acc + x } else { acc }  })
+            .fold(0, |acc, (_, x)| { if let Some(x) = x { acc + x } else { acc }  })
     }
 
     pub fn mark_number(&mut self, number: usize) -> Result<bool, String> {
         let mut found_number = None;
 
-        for (row_index, row) in self.0.iter_mut().enumerate() {
-            for (item_index, item) in row.iter_mut().enumerate() {
-                if *item == Some(number) { 
-                    *item = None;
-                    found_number = Some((row_index, item_index));
-                }
+        for (pos, item) in self.0.iter_mut() {
+            if *item == Some(number) { 
+                *item = None;
+                found_number = Some(pos);
             }
         }
         
         match found_number {
-            Some((row, column)) => {
-                let is_winning_row = self.is_winning_row(row)?;
-                let is_winning_column = self.is_winning_column(column)?;
+            Some(pos) => {
+                let is_winning_row = self.is_winning_row(pos.row)?;
+                let is_winning_column = self.is_winning_column(pos.col)?;
                 Ok(is_winning_row || is_winning_column)
             }
             None => Ok(false)
@@ -39,17 +38,14 @@ impl Board {
     }
 
     pub fn is_winning_row(&self, index: usize) -> Result<bool, String> {
-        let row = self.0.get(index).ok_or(String::from("Invalid row index"))?;
+        let row = self.0.row(index).ok_or(String::from("Invalid row index"))?;
         let some_exists = row.iter().find(|item| item.is_some());
         Ok(some_exists.is_none())
     }
 
     pub fn is_winning_column(&self, index: usize) -> Result<bool, String> {
-        for row in self.0.iter() {
-            let item = row.get(index).ok_or(String::from("Invalid item index"))?;
-            if item.is_some() { return Ok(false) }
-        }
-        Ok(true)
+        let mut column = self.0.column(index).ok_or(String::from("Invalid item index"))?;
+        Ok(column.all(|item| item.is_none()))
     }
 }
 
@@ -102,7 +98,7 @@ impl Bingo {
                 let numbers = Bingo::read_numbers(line, true)?;
                 board_rows.push(numbers);
                 if board_rows.len() == board_size { 
-                    boards.push(Board::new(&board_rows));
+                    boards.push(Board::new(&board_rows)?);
                     board_rows = Vec::new();
                  }
             }
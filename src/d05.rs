@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+use crate::grid::{Grid, Pos};
+
+pub mod geometry;
+pub mod render;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub struct Point { 
     x: usize,
     y: usize,
@@ -8,6 +13,13 @@ pub struct Point {
 
 impl Point {
     pub fn new(x: usize, y: usize) -> Self { Self { x, y } }
+
+    /// Cell of a vents field starting at `origin`, `x` being the column and `y` the row.
+    fn to_pos(self, origin: Point) -> Option<Pos> {
+        Some(Pos::new(self.y.checked_sub(origin.y)?, self.x.checked_sub(origin.x)?))
+    }
+
+    fn from_pos(pos: Pos, origin: Point) -> Self { Self::new(origin.x + pos.col, origin.y + pos.row) }
 }
 
 #[derive(Debug)]
@@ -61,14 +73,77 @@ impl VentsLine {
     }
 }
 
-type VentsState = HashMap<Point, usize>;
+/// Largest bounding box stored as a grid; sparser fields are stored point by point.
+const MAX_GRID_CELLS: usize = 1 << 22;
+
+#[derive(Debug)]
+enum Cells {
+    Grid(Grid<usize>),
+    Sparse(HashMap<Point, usize>),
+}
+
+/// Number of lines covering each point. The field is a grid over the bounding box of the
+/// lines, starting at `origin`, unless that box is too large to allocate.
+#[derive(Debug)]
+pub struct VentsState {
+    origin: Point,
+    cells: Cells,
+}
+
+impl Default for VentsState {
+    fn default() -> Self {
+        Self { origin: Point::new(0, 0), cells: Cells::Grid(Grid::default()) }
+    }
+}
+
+impl VentsState {
 
-fn feed_vents_state(vents_lines: &Vec<VentsLine>) -> VentsState {
-    let mut state = VentsState::new();
+    fn with_bounds(min: Point, max: Point) -> Self {
+        let width = (max.x - min.x).checked_add(1);
+        let height = (max.y - min.y).checked_add(1);
+        let size = width.zip(height).and_then(|(width, height)| Some((width, height, width.checked_mul(height)?)));
+        let cells = match size {
+            Some((width, height, cells)) if cells <= MAX_GRID_CELLS => Cells::Grid(Grid::new(width, height, 0)),
+            _ => Cells::Sparse(HashMap::new()),
+        };
+        Self { origin: min, cells }
+    }
 
-    for vent_line in vents_lines.into_iter() {
+    fn add(&mut self, point: Point) {
+        match &mut self.cells {
+            Cells::Grid(grid) => {
+                if let Some(n) = point.to_pos(self.origin).and_then(|pos| grid.get_mut(pos)) { *n += 1 }
+            }
+            Cells::Sparse(map) => *map.entry(point).or_insert(0) += 1,
+        }
+    }
+
+    /// Number of lines covering the point.
+    pub fn get(&self, point: Point) -> usize {
+        match &self.cells {
+            Cells::Grid(grid) => point.to_pos(self.origin).and_then(|pos| grid.get(pos)).copied().unwrap_or(0),
+            Cells::Sparse(map) => map.get(&point).copied().unwrap_or(0),
+        }
+    }
+
+    /// Points of the field with their coverage, in no particular order; uncovered points may be left out.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (Point, usize)> + '_> {
+        match &self.cells {
+            Cells::Grid(grid) => Box::new(grid.iter().map(move |(pos, &n)| (Point::from_pos(pos, self.origin), n))),
+            Cells::Sparse(map) => Box::new(map.iter().map(|(&point, &n)| (point, n))),
+        }
+    }
+}
+
+pub fn feed_vents_state(vents_lines: &[VentsLine]) -> VentsState {
+    let ends = || vents_lines.iter().flat_map(|l| [l.from, l.to]);
+    let (min_x, max_x) = (ends().map(|p| p.x).min().unwrap_or(0), ends().map(|p| p.x).max().unwrap_or(0));
+    let (min_y, max_y) = (ends().map(|p| p.y).min().unwrap_or(0), ends().map(|p| p.y).max().unwrap_or(0));
+    let mut state = VentsState::with_bounds(Point::new(min_x, min_y), Point::new(max_x, max_y));
+
+    for vent_line in vents_lines.iter() {
         let points = vent_line.get_points();
-        points.into_iter().for_each(|p| { *state.entry(p).or_insert(0) += 1; });
+        points.into_iter().for_each(|p| state.add(p));
     }
 
     state
@@ -76,7 +151,7 @@ fn feed_vents_state(vents_lines: &Vec<VentsLine>) -> VentsState {
 
 pub fn find_number_of_points_that_overlap(vents_lines: &Vec<VentsLine>) -> usize {
     let state = feed_vents_state(vents_lines);
-    let overlaps: Vec<_> = state.into_iter().filter(|&(_, n)| { n > 1 }).collect();
+    let overlaps: Vec<_> = state.iter().filter(|&(_, n)| { n > 1 }).collect();
     overlaps.len()
 }
 
@@ -156,6 +231,24 @@ mod tests {
         assert_eq!(v.get_points(), expected_points);
     }
 
+    #[test]
+    fn feed_far_and_large_fields() {
+        let far = vec![
+            VentsLine { from: Point::new(1000, 2000), to: Point::new(1002, 2000) },
+            VentsLine { from: Point::new(1001, 1999), to: Point::new(1001, 2001) },
+        ];
+        let state = feed_vents_state(&far);
+        assert_eq!(state.get(Point::new(1001, 2000)), 2);
+        assert_eq!(state.get(Point::new(0, 0)), 0);
+        assert_eq!(state.iter().count(), 9);
+
+        let large = vec![
+            VentsLine { from: Point::new(0, 0), to: Point::new(50000, 50000) },
+            VentsLine { from: Point::new(0, 50000), to: Point::new(50000, 0) },
+        ];
+        assert_eq!(find_number_of_points_that_overlap(&large), 1);
+    }
+
     #[test]
     fn day_5_part_1_solution() {
         let data: Vec<VentsLine> = data::read_input_data("./data/d05/data.txt").expect("Invalid input data");
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use super::{feed_vents_state, Point, VentsLine, VentsState};
+
+#[derive(Debug, PartialEq)]
+pub struct Crossing {
+    pub line: usize,
+    pub points: Vec<Point>,
+}
+
+/// Parsed vent lines indexed by the lattice points they cover.
+pub struct VentsSet {
+    lines: Vec<VentsLine>,
+    state: VentsState,
+    lines_at: HashMap<Point, Vec<usize>>,
+}
+
+impl VentsSet {
+
+    pub fn new(lines: Vec<VentsLine>) -> Self {
+        let state = feed_vents_state(&lines);
+        let mut lines_at: HashMap<Point, Vec<usize>> = HashMap::new();
+        for (index, line) in lines.iter().enumerate() {
+            for point in line.get_points() {
+                lines_at.entry(point).or_default().push(index);
+            }
+        }
+        Self { lines, state, lines_at }
+    }
+
+    pub fn lines(&self) -> &[VentsLine] {
+        &self.lines
+    }
+
+    pub fn state(&self) -> &VentsState {
+        &self.state
+    }
+
+    /// Other lines sharing at least one point with the given line, ordered by line index.
+    pub fn crossings(&self, index: usize) -> Result<Vec<Crossing>, String> {
+        let line = self.lines.get(index).ok_or(String::from("Invalid line index"))?;
+        let mut crossings: HashMap<usize, Vec<Point>> = HashMap::new();
+        for point in line.get_points() {
+            for &other in self.lines_at.get(&point).into_iter().flatten() {
+                if other != index { crossings.entry(other).or_default().push(point) }
+            }
+        }
+        let mut crossings: Vec<Crossing> = crossings.into_iter()
+            .map(|(line, points)| Crossing { line, points })
+            .collect();
+        crossings.sort_by_key(|c| c.line);
+        Ok(crossings)
+    }
+
+    /// Number of lines covering each point of the given line, in `get_points` order.
+    pub fn coverage_along(&self, index: usize) -> Result<Vec<(Point, usize)>, String> {
+        let line = self.lines.get(index).ok_or(String::from("Invalid line index"))?;
+        let coverage = line.get_points().into_iter()
+            .map(|p| (p, self.state.get(p)))
+            .collect();
+        Ok(coverage)
+    }
+
+    /// The `k` most covered points, hottest first; ties are broken by point order.
+    pub fn hot_spots(&self, k: usize) -> Vec<(Point, usize)> {
+        let mut points: Vec<(Point, usize)> = self.state.iter()
+            .filter(|&(_, n)| n > 0)
+            .collect();
+        points.sort_by(|(pa, na), (pb, nb)| nb.cmp(na).then(pa.cmp(pb)));
+        points.truncate(k);
+        points
+    }
+
+    pub fn points_covered_by_at_least(&self, k: usize) -> usize {
+        self.state.iter().filter(|&(_, n)| n > 0 && n >= k).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::d05::find_number_of_points_that_overlap;
+    use crate::data;
+    use super::*;
+
+    fn example_set() -> VentsSet {
+        let lines = vec![
+            VentsLine { from: Point::new(0, 0), to: Point::new(4, 4) },
+            VentsLine { from: Point::new(0, 4), to: Point::new(4, 0) },
+            VentsLine { from: Point::new(1, 1), to: Point::new(3, 1) },
+            VentsLine { from: Point::new(2, 0), to: Point::new(2, 4) },
+        ];
+        VentsSet::new(lines)
+    }
+
+    #[test]
+    fn find_crossings() {
+        let set = example_set();
+        let expected = vec![
+            Crossing { line: 1, points: vec![Point::new(2, 2)] },
+            Crossing { line: 2, points: vec![Point::new(1, 1)] },
+            Crossing { line: 3, points: vec![Point::new(2, 2)] },
+        ];
+        assert_eq!(set.crossings(0), Ok(expected));
+        assert_eq!(set.crossings(2).map(|c| c.len()), Ok(3));
+        assert!(set.crossings(4).is_err());
+    }
+
+    #[test]
+    fn find_coverage_and_hot_spots() {
+        let set = example_set();
+        let coverage: Vec<usize> = set.coverage_along(3).expect("Invalid line").into_iter().map(|(_, n)| n).collect();
+        assert_eq!(coverage, vec![1, 2, 3, 1, 1]);
+        assert_eq!(set.hot_spots(2), vec![(Point::new(2, 2), 3), (Point::new(1, 1), 2)]);
+        assert_eq!(set.points_covered_by_at_least(2), 4);
+        assert_eq!(set.points_covered_by_at_least(3), 1);
+        assert_eq!(set.points_covered_by_at_least(4), 0);
+    }
+
+    #[test]
+    fn covered_by_at_least_two_matches_overlaps() {
+        let data: Vec<VentsLine> = data::read_input_data("./data/d05/data.txt").expect("Invalid input data");
+        let number_of_overlaps = find_number_of_points_that_overlap(&data);
+        let set = VentsSet::new(data);
+        assert_eq!(set.points_covered_by_at_least(2), number_of_overlaps);
+    }
+}
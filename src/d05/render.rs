@@ -0,0 +1,170 @@
+use std::io::{Error, ErrorKind, Write};
+
+use super::{Point, VentsState};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+
+    pub fn new(min: Point, max: Point) -> Result<Self, String> {
+        if min.x > max.x || min.y > max.y { return Err(String::from("Invalid bounding box")) }
+        Ok(Self { min, max })
+    }
+
+    /// Smallest box containing every point covered by at least one line.
+    pub fn around(state: &VentsState) -> Option<Self> {
+        let covered = || state.iter().filter(|&(_, n)| n > 0).map(|(point, _)| point);
+        let min_x = covered().map(|p| p.x).min()?;
+        let min_y = covered().map(|p| p.y).min()?;
+        let max_x = covered().map(|p| p.x).max()?;
+        let max_y = covered().map(|p| p.y).max()?;
+        Some(Self { min: Point::new(min_x, min_y), max: Point::new(max_x, max_y) })
+    }
+
+    pub fn width(&self) -> usize {
+        self.max.x - self.min.x + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.max.y - self.min.y + 1
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Point>> {
+        let (min_x, max_x) = (self.min.x, self.max.x);
+        (self.min.y..=self.max.y).map(move |y| (min_x..=max_x).map(move |x| Point::new(x, y)))
+    }
+}
+
+/// Renders the field as the puzzle does: `.` for no line, otherwise the number of lines
+/// covering the point (`#` once it no longer fits in a single digit). Without bounds, the
+/// box around the covered points is drawn.
+pub fn render_ascii(state: &VentsState, bounds: Option<BoundingBox>) -> String {
+    let bounds = match bounds.or_else(|| BoundingBox::around(state)) {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+
+    let mut output = String::with_capacity((bounds.width() + 1) * bounds.height());
+    for row in bounds.rows() {
+        for point in row {
+            let c = match state.get(point) {
+                0 => '.',
+                n if n < 10 => char::from_digit(n as u32, 10).unwrap_or('#'),
+                _ => '#',
+            };
+            output.push(c);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Writes the field as a binary (P5) PGM heatmap, row by row. Every pixel holds the number of
+/// lines covering the point, saturated at 255, and the max value is the hottest point in view.
+/// Without bounds, the box around the covered points is drawn.
+pub fn write_pgm<W: Write>(state: &VentsState, bounds: Option<BoundingBox>, writer: &mut W) -> Result<(), Error> {
+    let bounds = bounds.or_else(|| BoundingBox::around(state))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Empty vents field"))?;
+
+    let max_value = state.iter()
+        .filter(|&(point, _)| bounds.contains(point))
+        .map(|(_, n)| n.min(255))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    write!(writer, "P5\n{} {}\n{}\n", bounds.width(), bounds.height(), max_value)?;
+    let mut pixels = Vec::with_capacity(bounds.width());
+    for row in bounds.rows() {
+        pixels.clear();
+        pixels.extend(row.map(|point| state.get(point).min(255) as u8));
+        writer.write_all(&pixels)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::d05::{feed_vents_state, VentsLine};
+    use super::*;
+
+    const EXAMPLE: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
+    fn example_state() -> VentsState {
+        let lines: Vec<VentsLine> = EXAMPLE.lines().map(|l| l.parse().expect("Invalid line")).collect();
+        feed_vents_state(&lines)
+    }
+
+    #[test]
+    fn render_example_as_ascii() {
+        let expected = "\
+1.1....11.
+.111...2..
+..2.1.111.
+...1.2.2..
+.112313211
+...1.2....
+..1...1...
+.1.....1..
+1.......1.
+222111....
+";
+        assert_eq!(render_ascii(&example_state(), None), expected);
+    }
+
+    #[test]
+    fn render_cropped_ascii() {
+        let bounds = BoundingBox::new(Point::new(3, 3), Point::new(6, 5)).expect("Invalid bounds");
+        assert_eq!(render_ascii(&example_state(), Some(bounds)), "1.2.\n2313\n1.2.\n");
+        assert!(BoundingBox::new(Point::new(3, 3), Point::new(2, 5)).is_err());
+    }
+
+    #[test]
+    fn write_cropped_pgm() {
+        let bounds = BoundingBox::new(Point::new(4, 4), Point::new(6, 5)).expect("Invalid bounds");
+        let mut output = Vec::new();
+        write_pgm(&example_state(), Some(bounds), &mut output).expect("Unable to write image");
+        let mut expected = b"P5\n3 2\n3\n".to_vec();
+        expected.extend_from_slice(&[3, 1, 3, 0, 2, 0]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn write_empty_pgm() {
+        let mut output = Vec::new();
+        assert!(write_pgm(&VentsState::default(), None, &mut output).is_err());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn write_pgm_around_covered_points() {
+        let lines = vec![
+            VentsLine { from: Point::new(1000, 2000), to: Point::new(1002, 2000) },
+            VentsLine { from: Point::new(1001, 1999), to: Point::new(1001, 2001) },
+        ];
+        let mut output = Vec::new();
+        write_pgm(&feed_vents_state(&lines), None, &mut output).expect("Unable to write image");
+        let mut expected = b"P5\n3 3\n2\n".to_vec();
+        expected.extend_from_slice(&[0, 1, 0, 1, 2, 1, 0, 1, 0]);
+        assert_eq!(output, expected);
+        assert_eq!(render_ascii(&feed_vents_state(&lines), None), ".1.\n121\n.1.\n");
+    }
+}
@@ -1,32 +1,165 @@
-pub struct LanternfishList(Vec<usize>);
+pub mod fast_forward;
+pub mod series;
 
-impl std::str::FromStr for LanternfishList {
+/// Life cycle of a lanternfish. A fish whose timer reaches 0 resets it to `reset_timer` and
+/// spawns a newborn, which first spends `maturation_delay` days immature and then starts
+/// counting down from `newborn_timer`. With `max_lifespan` set, every fish dies once it has
+/// lived that many days (fish from the input start at age 0).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnModel {
+    reset_timer: usize,
+    newborn_timer: usize,
+    maturation_delay: usize,
+    max_lifespan: Option<usize>,
+}
+
+/// Largest state a model may need, so that simulating it stays affordable.
+const MAX_STATES: usize = 1 << 16;
+
+impl Default for SpawnModel {
+    fn default() -> Self {
+        Self { reset_timer: 6, newborn_timer: 8, maturation_delay: 0, max_lifespan: None }
+    }
+}
+
+impl SpawnModel {
+
+    pub fn new(reset_timer: usize, newborn_timer: usize, maturation_delay: usize, max_lifespan: Option<usize>) -> Result<Self, String> {
+        if max_lifespan == Some(0) { return Err(String::from("Invalid lifespan")) }
+        let states = usize::max(reset_timer, newborn_timer)
+            .checked_add(1)
+            .and_then(|n| n.checked_add(maturation_delay))
+            .and_then(|stages| stages.checked_mul(max_lifespan.unwrap_or(1)));
+        match states {
+            Some(states) if states <= MAX_STATES => Ok(Self { reset_timer, newborn_timer, maturation_delay, max_lifespan }),
+            _ => Err(String::from("Too many states")),
+        }
+    }
+
+    pub fn max_timer(&self) -> usize {
+        usize::max(self.reset_timer, self.newborn_timer)
+    }
+
+    fn stages(&self) -> usize {
+        self.max_timer() + 1 + self.maturation_delay
+    }
+
+    /// Size of the simulated state: one bucket per stage (timer or immature day) and age.
+    pub fn number_of_states(&self) -> usize {
+        self.stages() * self.max_lifespan.unwrap_or(1)
+    }
+
+    /// Bucket holding a fish from the input with the given timer.
+    pub fn state_of_timer(&self, timer: usize) -> Option<usize> {
+        if timer <= self.max_timer() { Some(timer) } else { None }
+    }
+
+    /// Buckets that a single fish in `state` contributes to on the next day.
+    pub fn successors(&self, state: usize) -> Vec<usize> {
+        let stages = self.stages();
+        let (age, stage) = (state / stages, state % stages);
+        let newborn_stage = if self.maturation_delay > 0 { stages - 1 } else { self.newborn_timer };
+
+        let (next_stage, spawns) = if stage == 0 {
+            (self.reset_timer, true)
+        } else if stage <= self.max_timer() {
+            (stage - 1, false)
+        } else if stage == self.max_timer() + 1 {
+            (self.newborn_timer, false)
+        } else {
+            (stage - 1, false)
+        };
+
+        let mut successors = Vec::with_capacity(2);
+        match self.max_lifespan {
+            Some(lifespan) if age + 1 >= lifespan => (),
+            Some(_) => successors.push((age + 1) * stages + next_stage),
+            None => successors.push(next_stage),
+        }
+        if spawns { successors.push(newborn_stage) }
+        successors
+    }
+
+    /// Counts of fish per bucket for the given timers.
+    pub fn initial_state(&self, lanternfish_data: &[usize]) -> Result<Vec<usize>, String> {
+        let mut state = vec![0; self.number_of_states()];
+        for &timer in lanternfish_data {
+            let index = self.state_of_timer(timer).ok_or(String::from("Invalid timer"))?;
+            state[index] += 1;
+        }
+        Ok(state)
+    }
+}
+
+impl std::str::FromStr for SpawnModel {
 
     type Err = String;
 
+    /// Parses `key=value` pairs separated by commas, e.g. `reset=6,newborn=8,maturation=0,lifespan=40`.
+    /// Missing keys keep their default value.
     fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut model = SpawnModel::default();
+        for item in data.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let mut item = item.splitn(2, '=');
+            let key = item.next().ok_or(String::from("No key"))?;
+            let value = item.next().ok_or(String::from("No value"))?;
+            let value: usize = value.trim().parse().map_err(|_| String::from("Invalid value"))?;
+            match key.trim() {
+                "reset" => model.reset_timer = value,
+                "newborn" => model.newborn_timer = value,
+                "maturation" => model.maturation_delay = value,
+                "lifespan" => model.max_lifespan = Some(value),
+                _ => return Err(String::from("Invalid key")),
+            }
+        }
+        SpawnModel::new(model.reset_timer, model.newborn_timer, model.maturation_delay, model.max_lifespan)
+    }
+}
+
+pub struct LanternfishList(Vec<usize>);
+
+impl LanternfishList {
+
+    pub fn parse_with_model(data: &str, model: &SpawnModel) -> Result<Self, String> {
         let mut items = Vec::new();
         for item in data.split(',') {
-            let item: usize = item.parse().map_err(|_| String::from("Invalid item"))?;
+            let item: usize = item.trim().parse().map_err(|_| String::from("Invalid item"))?;
+            model.state_of_timer(item).ok_or(String::from("Invalid timer"))?;
             items.push(item);
         }
         Ok(LanternfishList(items))
     }
 }
 
-pub fn simulate_lanternfish_grow(lanternfish_data: &Vec<usize>, number_of_cycles: usize) -> usize {
-    let mut data = [0; 9];
+impl std::str::FromStr for LanternfishList {
+
+    type Err = String;
 
-    for &n in lanternfish_data {
-        data[n] += 1;
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        LanternfishList::parse_with_model(data, &SpawnModel::default())
     }
+}
+
+pub fn simulate_lanternfish_grow(lanternfish_data: &[usize], number_of_cycles: usize) -> Result<usize, String> {
+    simulate_lanternfish_grow_with_model(lanternfish_data, &SpawnModel::default(), number_of_cycles)
+}
 
-    (0..number_of_cycles).into_iter().for_each(|_| {
-        data.rotate_left(1);
-        data[6] += data[8];
-    });
+pub fn simulate_lanternfish_grow_with_model(lanternfish_data: &[usize], model: &SpawnModel, number_of_cycles: usize) -> Result<usize, String> {
+    let mut data = model.initial_state(lanternfish_data)?;
+    let successors: Vec<Vec<usize>> = (0..data.len()).map(|state| model.successors(state)).collect();
 
-    data.into_iter().fold(0, |acc, n| acc + n)
+    let overflow = || String::from("Population overflows usize");
+    for _ in 0..number_of_cycles {
+        let mut next = vec![0usize; data.len()];
+        for (state, &count) in data.iter().enumerate().filter(|&(_, &count)| count > 0) {
+            for &s in &successors[state] {
+                next[s] = next[s].checked_add(count).ok_or_else(overflow)?;
+            }
+        }
+        data = next;
+    }
+
+    data.into_iter().try_fold(0usize, |acc, n| acc.checked_add(n)).ok_or_else(overflow)
 }
 
 #[cfg(test)]
@@ -38,14 +171,49 @@ mod tests {
     #[test]
     fn day_6_part_1_solution() {
         let lanternfish_data: LanternfishList = data::read_one_line_input_data("./data/d06/data.txt").expect("Invalid input data");
-        let simulation_results = simulate_lanternfish_grow(&lanternfish_data.0, 80);
+        let simulation_results = simulate_lanternfish_grow(&lanternfish_data.0, 80).expect("Invalid input data");
         assert_eq!(simulation_results, 359344);
     }
 
     #[test]
     fn day_6_part_2_solution() {
         let lanternfish_data: LanternfishList = data::read_one_line_input_data("./data/d06/data.txt").expect("Invalid input data");
-        let simulation_results = simulate_lanternfish_grow(&lanternfish_data.0, 256);
+        let simulation_results = simulate_lanternfish_grow(&lanternfish_data.0, 256).expect("Invalid input data");
         assert_eq!(simulation_results, 1629570219571);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reject_timers_outside_of_model() {
+        assert!("3,4,9".parse::<LanternfishList>().is_err());
+        let model: SpawnModel = "reset=10,newborn=12".parse().expect("Invalid model");
+        assert!(LanternfishList::parse_with_model("3,4,12", &model).is_ok());
+        assert!(LanternfishList::parse_with_model("3,4,13", &model).is_err());
+        assert!("lifespan=0".parse::<SpawnModel>().is_err());
+        assert!("speed=3".parse::<SpawnModel>().is_err());
+        assert!("reset=18446744073709551615".parse::<SpawnModel>().is_err());
+        assert!("maturation=18446744073709551614".parse::<SpawnModel>().is_err());
+        assert!("lifespan=4000000000000000000".parse::<SpawnModel>().is_err());
+        assert!("reset=1000,lifespan=1000".parse::<SpawnModel>().is_err());
+    }
+
+    #[test]
+    fn simulate_custom_models() {
+        // Maturation delay is equivalent to a longer newborn timer.
+        let delayed: SpawnModel = "newborn=6,maturation=2".parse().expect("Invalid model");
+        let data = vec![3, 4, 3, 1, 2];
+        let expected = simulate_lanternfish_grow(&data, 80).expect("Invalid input data");
+        assert_eq!(simulate_lanternfish_grow_with_model(&data, &delayed, 80), Ok(expected));
+
+        // A fish living three days spawns once on day 1 and dies on day 3, its child on day 4.
+        let short_lived: SpawnModel = "lifespan=3".parse().expect("Invalid model");
+        let population: Vec<usize> = (0..5)
+            .map(|days| simulate_lanternfish_grow_with_model(&[0], &short_lived, days).expect("Invalid input data"))
+            .collect();
+        assert_eq!(population, vec![1, 2, 2, 1, 0]);
+    }
+
+    #[test]
+    fn report_population_overflow() {
+        assert!(simulate_lanternfish_grow(&[3, 4, 3, 1, 2], 600).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::bigint::BigUint;
+use super::SpawnModel;
+
+trait Arithmetic {
+    type Value: Clone;
+
+    fn zero(&self) -> Self::Value;
+    fn one(&self) -> Self::Value;
+    fn count(&self, n: u64) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Option<Self::Value>;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Option<Self::Value>;
+}
+
+struct Modular(u64);
+
+impl Arithmetic for Modular {
+    type Value = u64;
+
+    fn zero(&self) -> u64 { 0 }
+    fn one(&self) -> u64 { 1 % self.0 }
+    fn count(&self, n: u64) -> u64 { n % self.0 }
+
+    fn add(&self, a: &u64, b: &u64) -> Option<u64> {
+        Some(((*a as u128 + *b as u128) % self.0 as u128) as u64)
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> Option<u64> {
+        Some(((*a as u128 * *b as u128) % self.0 as u128) as u64)
+    }
+}
+
+struct Checked;
+
+impl Arithmetic for Checked {
+    type Value = u128;
+
+    fn zero(&self) -> u128 { 0 }
+    fn one(&self) -> u128 { 1 }
+    fn count(&self, n: u64) -> u128 { n as u128 }
+    fn add(&self, a: &u128, b: &u128) -> Option<u128> { a.checked_add(*b) }
+    fn mul(&self, a: &u128, b: &u128) -> Option<u128> { a.checked_mul(*b) }
+}
+
+struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+
+    fn zero(&self) -> BigUint { BigUint::zero() }
+    fn one(&self) -> BigUint { BigUint::one() }
+    fn count(&self, n: u64) -> BigUint { BigUint::from(n) }
+    fn add(&self, a: &BigUint, b: &BigUint) -> Option<BigUint> { Some(a + b) }
+    fn mul(&self, a: &BigUint, b: &BigUint) -> Option<BigUint> { Some(a * b) }
+}
+
+/// Square matrix stored row-major.
+struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Matrix<T> {
+
+    /// One day of the simulation under the given model.
+    fn transition<A: Arithmetic<Value = T>>(model: &SpawnModel, arithmetic: &A) -> Option<Self> {
+        let size = model.number_of_states();
+        let mut cells = vec![arithmetic.zero(); size * size];
+        for state in 0..size {
+            for next in model.successors(state) {
+                let cell = &mut cells[next * size + state];
+                *cell = arithmetic.add(cell, &arithmetic.one())?;
+            }
+        }
+        Some(Self { size, cells })
+    }
+
+    fn multiply<A: Arithmetic<Value = T>>(&self, other: &Self, arithmetic: &A) -> Option<Self> {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for row in 0..self.size {
+            for column in 0..self.size {
+                let mut cell = arithmetic.zero();
+                for k in 0..self.size {
+                    let product = arithmetic.mul(&self.cells[row * self.size + k], &other.cells[k * self.size + column])?;
+                    cell = arithmetic.add(&cell, &product)?;
+                }
+                cells.push(cell);
+            }
+        }
+        Some(Self { size: self.size, cells })
+    }
+
+    fn apply<A: Arithmetic<Value = T>>(&self, vector: &[T], arithmetic: &A) -> Option<Vec<T>> {
+        let mut output = Vec::with_capacity(self.size);
+        for row in 0..self.size {
+            let mut cell = arithmetic.zero();
+            for (k, value) in vector.iter().enumerate() {
+                let product = arithmetic.mul(&self.cells[row * self.size + k], value)?;
+                cell = arithmetic.add(&cell, &product)?;
+            }
+            output.push(cell);
+        }
+        Some(output)
+    }
+}
+
+/// Largest state fast-forwarded: the transition matrix has its square number of cells, and
+/// each of the O(log days) products takes its cube number of operations.
+const MAX_STATES: usize = 256;
+
+/// Counts of fish per bucket, for models small enough to fast-forward.
+fn initial_counts(lanternfish_data: &[usize], model: &SpawnModel) -> Result<Vec<usize>, String> {
+    if model.number_of_states() > MAX_STATES { return Err(String::from("Model too large to fast-forward")) }
+    model.initial_state(lanternfish_data)
+}
+
+/// Advances the state by `days` using O(log days) matrix products.
+fn fast_forward<A: Arithmetic>(counts: &[usize], model: &SpawnModel, days: u64, arithmetic: &A) -> Option<A::Value> {
+    let mut state: Vec<A::Value> = counts.iter().map(|&n| arithmetic.count(n as u64)).collect();
+    let mut step = Matrix::transition(model, arithmetic)?;
+    let mut days = days;
+
+    while days > 0 {
+        if days & 1 == 1 { state = step.apply(&state, arithmetic)?; }
+        days >>= 1;
+        if days > 0 { step = step.multiply(&step, arithmetic)?; }
+    }
+
+    state.iter().try_fold(arithmetic.zero(), |acc, n| arithmetic.add(&acc, n))
+}
+
+/// Population after `days`, modulo `modulus`.
+pub fn simulate_lanternfish_grow_modulo(lanternfish_data: &[usize], model: &SpawnModel, days: u64, modulus: u64) -> Result<u64, String> {
+    if modulus == 0 { return Err(String::from("Invalid modulus")) }
+    let counts = initial_counts(lanternfish_data, model)?;
+    fast_forward(&counts, model, days, &Modular(modulus)).ok_or(String::from("Arithmetic error"))
+}
+
+/// Exact population after `days`, failing once any intermediate value no longer fits in `u128`.
+pub fn simulate_lanternfish_grow_checked(lanternfish_data: &[usize], model: &SpawnModel, days: u64) -> Result<u128, String> {
+    let counts = initial_counts(lanternfish_data, model)?;
+    fast_forward(&counts, model, days, &Checked).ok_or(String::from("Population overflows u128"))
+}
+
+/// Exact population after `days`, with no upper bound.
+pub fn simulate_lanternfish_grow_exact(lanternfish_data: &[usize], model: &SpawnModel, days: u64) -> Result<BigUint, String> {
+    let counts = initial_counts(lanternfish_data, model)?;
+    fast_forward(&counts, model, days, &Exact).ok_or(String::from("Arithmetic error"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::d06::{simulate_lanternfish_grow, simulate_lanternfish_grow_with_model, LanternfishList};
+    use crate::data;
+    use super::*;
+
+    const PRIME: u64 = 1_000_000_007;
+
+    fn input() -> Vec<usize> {
+        let lanternfish_data: LanternfishList = data::read_one_line_input_data("./data/d06/data.txt").expect("Invalid input data");
+        lanternfish_data.0
+    }
+
+    #[test]
+    fn fast_forward_matches_simulation() {
+        let data = input();
+        for days in [0, 1, 18, 80, 256] {
+            let expected = simulate_lanternfish_grow(&data, days as usize).expect("Invalid input data");
+            let model = SpawnModel::default();
+            assert_eq!(simulate_lanternfish_grow_checked(&data, &model, days), Ok(expected as u128));
+            assert_eq!(simulate_lanternfish_grow_modulo(&data, &model, days, PRIME), Ok(expected as u64 % PRIME));
+            assert_eq!(simulate_lanternfish_grow_exact(&data, &model, days).map(|n| n.to_u128()), Ok(Some(expected as u128)));
+        }
+    }
+
+    #[test]
+    fn exact_population_beyond_u128() {
+        let data = input();
+        let model = SpawnModel::default();
+        assert!(simulate_lanternfish_grow_checked(&data, &model, 2000).is_err());
+        let exact = simulate_lanternfish_grow_exact(&data, &model, 2000).expect("Invalid input data");
+        assert!(exact > BigUint::from(u128::MAX));
+        let remainder: u64 = exact.to_string().bytes().fold(0, |acc, b| (acc * 10 + (b - b'0') as u64) % PRIME);
+        assert_eq!(simulate_lanternfish_grow_modulo(&data, &model, 2000, PRIME), Ok(remainder));
+    }
+
+    #[test]
+    fn astronomically_long_horizon_follows_recurrence() {
+        // The transition matrix satisfies x^9 = x^2 + 1, hence P(n) = P(n - 7) + P(n - 9).
+        let data = input();
+        let model = SpawnModel::default();
+        let days = 1_000_000_000_000_000_000;
+        let p = |days| simulate_lanternfish_grow_modulo(&data, &model, days, PRIME).expect("Invalid input data");
+        assert_eq!(p(days), (p(days - 7) + p(days - 9)) % PRIME);
+    }
+
+    #[test]
+    fn fast_forward_custom_models() {
+        let data = vec![3, 4, 3, 1, 2];
+        for model in ["reset=4,newborn=5,maturation=3", "lifespan=20", "reset=4,newborn=1,lifespan=6"] {
+            let model: SpawnModel = model.parse().expect("Invalid model");
+            let expected = simulate_lanternfish_grow_with_model(&data, &model, 60).expect("Invalid input data");
+            assert_eq!(simulate_lanternfish_grow_checked(&data, &model, 60), Ok(expected as u128));
+        }
+    }
+
+    #[test]
+    fn reject_models_too_large_to_fast_forward() {
+        let largest: SpawnModel = "lifespan=28".parse().expect("Invalid model");
+        assert_eq!(simulate_lanternfish_grow_modulo(&[3], &largest, 1, PRIME), Ok(1));
+        let model: SpawnModel = "lifespan=400".parse().expect("Invalid model");
+        assert!(simulate_lanternfish_grow_modulo(&[3], &model, 1 << 20, PRIME).is_err());
+        assert!(simulate_lanternfish_grow_checked(&[3], &model, 10).is_err());
+        assert!(simulate_lanternfish_grow_exact(&[3], &model, 10).is_err());
+        assert!(simulate_lanternfish_grow_with_model(&[3], &model, 10).is_ok());
+    }
+
+    #[test]
+    fn reject_invalid_input() {
+        assert!(simulate_lanternfish_grow_modulo(&[3, 9], &SpawnModel::default(), 10, PRIME).is_err());
+        assert!(simulate_lanternfish_grow_modulo(&[3, 4], &SpawnModel::default(), 10, 0).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use std::io::{Error, Write};
+
+use crate::bigint::BigUint;
+use super::SpawnModel;
+use super::fast_forward::simulate_lanternfish_grow_exact;
+
+/// Population on a given day. `timers` holds the number of fish per timer value, followed by
+/// the immature fish per remaining maturation day (when the model has a maturation delay).
+#[derive(Debug, PartialEq, Clone)]
+pub struct DaySnapshot {
+    pub day: usize,
+    pub timers: Vec<usize>,
+    pub total: usize,
+}
+
+/// Day by day population, starting with day 0. The series ends once the population no longer
+/// fits in `usize`.
+pub struct PopulationSeries {
+    model: SpawnModel,
+    successors: Vec<Vec<usize>>,
+    state: Option<Vec<usize>>,
+    day: usize,
+}
+
+impl PopulationSeries {
+
+    pub fn new(lanternfish_data: &[usize], model: &SpawnModel) -> Result<Self, String> {
+        let state = model.initial_state(lanternfish_data)?;
+        let successors = (0..state.len()).map(|s| model.successors(s)).collect();
+        Ok(Self { model: model.clone(), successors, state: Some(state), day: 0 })
+    }
+
+    fn snapshot(&self, state: &[usize]) -> Option<DaySnapshot> {
+        let mut timers = vec![0usize; self.model.stages()];
+        for (index, &count) in state.iter().enumerate() {
+            let stage = &mut timers[index % self.model.stages()];
+            *stage = stage.checked_add(count)?;
+        }
+        let total = timers.iter().try_fold(0usize, |acc, &n| acc.checked_add(n))?;
+        Some(DaySnapshot { day: self.day, timers, total })
+    }
+
+    fn step(&self, state: &[usize]) -> Option<Vec<usize>> {
+        let mut next = vec![0usize; state.len()];
+        for (index, &count) in state.iter().enumerate().filter(|&(_, &count)| count > 0) {
+            for &s in &self.successors[index] {
+                next[s] = next[s].checked_add(count)?;
+            }
+        }
+        Some(next)
+    }
+
+    /// Writes the first `days + 1` snapshots as CSV, one row per day.
+    pub fn write_csv<W: Write>(self, days: usize, writer: &mut W) -> Result<(), Error> {
+        let timers = (0..=self.model.max_timer()).map(|t| format!("t{}", t));
+        let immature = (1..=self.model.maturation_delay).map(|d| format!("m{}", d));
+        let header: Vec<String> = timers.chain(immature).collect();
+        writeln!(writer, "day,{},total", header.join(","))?;
+
+        for snapshot in self.take(days + 1) {
+            let timers: Vec<String> = snapshot.timers.iter().map(|n| n.to_string()).collect();
+            writeln!(writer, "{},{},{}", snapshot.day, timers.join(","), snapshot.total)?;
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for PopulationSeries {
+    type Item = DaySnapshot;
+
+    fn next(&mut self) -> Option<DaySnapshot> {
+        let state = self.state.take()?;
+        let snapshot = self.snapshot(&state);
+        if snapshot.is_some() {
+            self.state = self.step(&state);
+            self.day += 1;
+        }
+        snapshot
+    }
+}
+
+/// First day on which the population is strictly greater than `threshold`, found by
+/// exponential and then binary search over the fast-forward path. Only models without
+/// a lifespan are supported, as the population never shrinks there.
+pub fn first_day_exceeding(lanternfish_data: &[usize], model: &SpawnModel, threshold: &BigUint) -> Result<Option<u64>, String> {
+    if model.max_lifespan.is_some() { return Err(String::from("Population is not monotonic with a lifespan")) }
+    let population = |days| simulate_lanternfish_grow_exact(lanternfish_data, model, days);
+
+    if population(0)?.is_zero() { return Ok(None) }
+    if population(0)? > *threshold { return Ok(Some(0)) }
+
+    let (mut low, mut high) = (0u64, 1u64);
+    while population(high)? <= *threshold {
+        low = high;
+        high = high.checked_mul(2).ok_or(String::from("Day overflow"))?;
+    }
+
+    // Invariant: population(low) <= threshold < population(high).
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if population(middle)? > *threshold { high = middle } else { low = middle }
+    }
+    Ok(Some(high))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const EXAMPLE: [usize; 5] = [3, 4, 3, 1, 2];
+
+    #[test]
+    fn iterate_daily_histograms() {
+        let series = PopulationSeries::new(&EXAMPLE, &SpawnModel::default()).expect("Invalid input data");
+        let days: Vec<DaySnapshot> = series.take(81).collect();
+        assert_eq!(days[0].timers, vec![0, 1, 1, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(days[1], DaySnapshot { day: 1, timers: vec![1, 1, 2, 1, 0, 0, 0, 0, 0], total: 5 });
+        assert_eq!(days[2], DaySnapshot { day: 2, timers: vec![1, 2, 1, 0, 0, 0, 1, 0, 1], total: 6 });
+        assert_eq!(days[18].total, 26);
+        assert_eq!(days[80].total, 5934);
+    }
+
+    #[test]
+    fn series_ends_on_overflow() {
+        let series = PopulationSeries::new(&EXAMPLE, &SpawnModel::default()).expect("Invalid input data");
+        let last = series.last().expect("Empty series");
+        assert!(last.day > 256 && last.day < 1000);
+    }
+
+    #[test]
+    fn export_csv() {
+        let model: SpawnModel = "reset=2,newborn=2,maturation=1".parse().expect("Invalid model");
+        let series = PopulationSeries::new(&[0, 2], &model).expect("Invalid input data");
+        let mut output = Vec::new();
+        series.write_csv(2, &mut output).expect("Unable to write csv");
+        let expected = "day,t0,t1,t2,m1,total\n0,1,0,1,0,2\n1,0,1,1,1,3\n2,1,1,1,0,3\n";
+        assert_eq!(String::from_utf8(output).expect("Invalid utf-8"), expected);
+    }
+
+    #[test]
+    fn find_first_day_exceeding() {
+        let model = SpawnModel::default();
+        let series: Vec<usize> = PopulationSeries::new(&EXAMPLE, &model).expect("Invalid input data")
+            .take(300)
+            .map(|s| s.total)
+            .collect();
+        for threshold in [0, 4, 5, 26, 5933, 5934, 1_000_000_000] {
+            let expected = series.iter().position(|&n| n > threshold).map(|d| d as u64);
+            assert_eq!(first_day_exceeding(&EXAMPLE, &model, &BigUint::from(threshold as u64)), Ok(expected));
+        }
+        assert_eq!(first_day_exceeding(&[], &model, &BigUint::from(10u64)), Ok(None));
+        assert!(first_day_exceeding(&EXAMPLE, &"lifespan=10".parse().expect("Invalid model"), &BigUint::zero()).is_err());
+    }
+}
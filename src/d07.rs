@@ -1,4 +1,35 @@
-pub struct Crabs(Vec<i32>);
+pub mod multi_target;
+
+/// A crab submarine. Moving a crab of weight `w` costs `w` times the fuel of a crab of weight 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crab {
+    pub position: i32,
+    pub weight: i64,
+}
+
+impl Crab {
+    pub fn new(position: i32) -> Self { Self { position, weight: 1 } }
+}
+
+impl std::str::FromStr for Crab {
+
+    type Err = String;
+
+    /// Parses `position` or `position:weight`.
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut data = data.trim().splitn(2, ':');
+        let position = data.next().ok_or(String::from("No position"))?;
+        let position: i32 = position.parse().map_err(|_| String::from("Invalid number"))?;
+        let weight: i64 = match data.next() {
+            Some(weight) => weight.parse().map_err(|_| String::from("Invalid weight"))?,
+            None => 1,
+        };
+        if weight < 1 { return Err(String::from("Invalid weight")) }
+        Ok(Crab { position, weight })
+    }
+}
+
+pub struct Crabs(Vec<Crab>);
 
 impl std::str::FromStr for Crabs {
 
@@ -7,27 +38,227 @@ impl std::str::FromStr for Crabs {
     fn from_str(data: &str) -> Result<Self, Self::Err> {
         let mut output = Vec::new();
         for n in data.split(',') {
-            let n: i32 = n.parse().map_err(|_| String::from("Invalid number"))?;
+            let n: Crab = n.parse()?;
             output.push(n);
         }
         Ok(Crabs(output))
     }
 }
 
-pub fn calculate_fuel_cost(crabs: &Vec<i32>, distance_to_cost: fn(i32) -> i32) -> i32 {
-    let min_position = *crabs.iter().min().unwrap_or(&i32::MIN);
-    let max_position = *crabs.iter().max().unwrap_or(&i32::MAX);
-    let mut target_cost = i32::MAX;
+/// Fuel spent by a crab of weight 1 to move `distance` steps. The fast solvers expect a cost
+/// that does not decrease and is convex in the distance.
+pub trait FuelCost {
+    fn cost(&self, distance: i64) -> i64;
+
+    /// `(a, b)` when moving `d` steps costs `(a * d^2 + b * d) / 2`, the numerator being always
+    /// even, so that the cost of many crabs can be summed from prefix sums of their positions.
+    fn quadratic_form(&self) -> Option<(i64, i64)> { None }
+}
 
-    for next_position in (min_position..max_position).into_iter() {
-        let cost = crabs.iter().fold(0, |acc, &next| {
-            let cost = distance_to_cost((next - next_position).abs());
-            cost + acc
-        });
-        if cost < target_cost { target_cost = cost }
+impl<F: Fn(i64) -> i64> FuelCost for F {
+    fn cost(&self, distance: i64) -> i64 { self(distance) }
+}
+
+/// Every step costs `rate`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Linear {
+    pub rate: i64,
+}
+
+impl Default for Linear {
+    fn default() -> Self { Self { rate: 1 } }
+}
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: i64) -> i64 { self.rate * distance }
+    fn quadratic_form(&self) -> Option<(i64, i64)> { Some((0, 2 * self.rate)) }
+}
+
+/// The n-th step costs `n * rate`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangular {
+    pub rate: i64,
+}
+
+impl Default for Triangular {
+    fn default() -> Self { Self { rate: 1 } }
+}
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: i64) -> i64 { self.rate * (distance * (distance + 1)) / 2 }
+    fn quadratic_form(&self) -> Option<(i64, i64)> { Some((self.rate, self.rate)) }
+}
+
+/// Moving `d` steps costs `coefficient * d^2`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quadratic {
+    pub coefficient: i64,
+}
+
+impl Default for Quadratic {
+    fn default() -> Self { Self { coefficient: 1 } }
+}
+
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: i64) -> i64 { self.coefficient * distance * distance }
+    fn quadratic_form(&self) -> Option<(i64, i64)> { Some((2 * self.coefficient, 0)) }
+}
+
+/// Per-step rate changing with the distance travelled so far. Each `(from, rate)` segment applies
+/// to the steps taken from distance `from` until the next segment starts. The cost is convex when
+/// the rates never decrease.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piecewise {
+    segments: Vec<(i64, i64)>,
+}
+
+impl Piecewise {
+
+    pub fn new(segments: Vec<(i64, i64)>) -> Result<Self, String> {
+        if segments.first().map(|&(from, _)| from) != Some(0) { return Err(String::from("First segment should start at 0")) }
+        if segments.windows(2).any(|w| w[0].0 >= w[1].0) { return Err(String::from("Segments should be increasing")) }
+        Ok(Self { segments })
+    }
+
+    pub fn is_convex(&self) -> bool {
+        self.segments.windows(2).all(|w| w[0].1 <= w[1].1) && self.segments.iter().all(|&(_, rate)| rate >= 0)
+    }
+}
+
+impl FuelCost for Piecewise {
+    fn cost(&self, distance: i64) -> i64 {
+        let ends = self.segments.iter().skip(1).map(|&(from, _)| from).chain(std::iter::once(i64::MAX));
+        self.segments.iter().zip(ends)
+            .map(|(&(from, rate), to)| (distance.min(to) - from).max(0) * rate)
+            .sum()
     }
+}
 
-    target_cost
+#[derive(Debug, PartialEq)]
+pub enum AlignmentError {
+    NoCrabs,
+    InvalidTargetCount(usize),
+}
+
+impl std::fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AlignmentError::NoCrabs => write!(f, "No crabs to align"),
+            AlignmentError::InvalidTargetCount(k) => write!(f, "Invalid number of alignment targets: {}", k),
+        }
+    }
+}
+
+/// Cheapest way to align the crabs. `positions` lists every optimal position in ascending
+/// order, `crab_costs` holds the fuel spent by each crab (in input order) to reach the first one.
+#[derive(Debug, PartialEq)]
+pub struct Alignment {
+    pub positions: Vec<i32>,
+    pub total_cost: i64,
+    pub crab_costs: Vec<i64>,
+}
+
+impl Alignment {
+
+    fn new<C: FuelCost>(crabs: &[Crab], positions: Vec<i32>, cost: &C) -> Result<Self, AlignmentError> {
+        let position = *positions.first().ok_or(AlignmentError::NoCrabs)?;
+        let crab_costs: Vec<i64> = crabs.iter().map(|crab| crab_cost(crab, position, cost)).collect();
+        let total_cost = crab_costs.iter().sum();
+        Ok(Self { positions, total_cost, crab_costs })
+    }
+
+    pub fn position(&self) -> i32 {
+        self.positions[0]
+    }
+}
+
+fn crab_cost<C: FuelCost>(crab: &Crab, position: i32, cost: &C) -> i64 {
+    crab.weight * cost.cost((crab.position as i64 - position as i64).abs())
+}
+
+fn fuel_cost_at<C: FuelCost>(crabs: &[Crab], position: i32, cost: &C) -> i64 {
+    crabs.iter().fold(0, |acc, crab| acc + crab_cost(crab, position, cost))
+}
+
+fn span(crabs: &[Crab]) -> Result<(i32, i32), AlignmentError> {
+    match (crabs.iter().map(|c| c.position).min(), crabs.iter().map(|c| c.position).max()) {
+        (Some(min), Some(max)) => Ok((min, max)),
+        _ => Err(AlignmentError::NoCrabs),
+    }
+}
+
+/// Keeps every candidate position sharing the lowest cost.
+fn cheapest_positions<C: FuelCost>(crabs: &[Crab], candidates: impl Iterator<Item = i32>, cost: &C) -> Vec<i32> {
+    let mut target_cost = i64::MAX;
+    let mut positions = Vec::new();
+
+    for next_position in candidates {
+        let next_cost = fuel_cost_at(crabs, next_position, cost);
+        if next_cost < target_cost {
+            target_cost = next_cost;
+            positions.clear();
+        }
+        if next_cost == target_cost { positions.push(next_position) }
+    }
+
+    positions
+}
+
+/// Tries every position between the leftmost and the rightmost crab.
+pub fn calculate_fuel_cost<C: FuelCost>(crabs: &[Crab], cost: &C) -> Result<Alignment, AlignmentError> {
+    let (min_position, max_position) = span(crabs)?;
+    let positions = cheapest_positions(crabs, min_position..=max_position, cost);
+    Alignment::new(crabs, positions, cost)
+}
+
+/// Linear cost: any position between the two weighted medians is optimal.
+pub fn calculate_linear_fuel_cost(crabs: &[Crab], cost: &Linear) -> Result<Alignment, AlignmentError> {
+    let mut sorted = crabs.to_vec();
+    sorted.sort_unstable_by_key(|c| c.position);
+    let total_weight: i64 = sorted.iter().map(|c| c.weight).sum();
+
+    let mut cumulative_weight = 0;
+    let (mut lower, mut upper) = (None, None);
+    for crab in &sorted {
+        cumulative_weight += crab.weight;
+        if lower.is_none() && cumulative_weight * 2 >= total_weight { lower = Some(crab.position) }
+        if upper.is_none() && cumulative_weight * 2 > total_weight { upper = Some(crab.position) }
+    }
+
+    let (lower, upper) = lower.zip(upper).ok_or(AlignmentError::NoCrabs)?;
+    Alignment::new(crabs, (lower..=upper).collect(), cost)
+}
+
+/// Triangular cost: the optimum lies within one position of the weighted mean.
+pub fn calculate_triangular_fuel_cost(crabs: &[Crab], cost: &Triangular) -> Result<Alignment, AlignmentError> {
+    if crabs.is_empty() { return Err(AlignmentError::NoCrabs) }
+    let sum: i64 = crabs.iter().map(|c| c.position as i64 * c.weight).sum();
+    let total_weight: i64 = crabs.iter().map(|c| c.weight).sum();
+    let mean = sum.div_euclid(total_weight) as i32;
+    let positions = cheapest_positions(crabs, mean - 1..=mean + 2, cost);
+    Alignment::new(crabs, positions, cost)
+}
+
+/// Works for any non-decreasing convex cost. The total cost is then convex in the position,
+/// so the optimum is where its slope stops being negative, found by binary search.
+pub fn calculate_convex_fuel_cost<C: FuelCost>(crabs: &[Crab], cost: &C) -> Result<Alignment, AlignmentError> {
+    let (mut low, mut high) = span(crabs)?;
+    let max_position = high;
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if fuel_cost_at(crabs, middle + 1, cost) >= fuel_cost_at(crabs, middle, cost) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+
+    let target_cost = fuel_cost_at(crabs, low, cost);
+    let positions = (low..=max_position)
+        .take_while(|&position| fuel_cost_at(crabs, position, cost) == target_cost)
+        .collect();
+    Alignment::new(crabs, positions, cost)
 }
 
 #[cfg(test)]
@@ -36,18 +267,83 @@ mod tests {
     use crate::data;
     use super::*;
 
+    const EXAMPLE: &str = "16,1,2,0,4,2,7,1,2,14";
+
     #[test]
     fn day_7_part_1_solution() {
         let data: Crabs = data::read_one_line_input_data("./data/d07/data.txt").expect("Invalid input data");
-        let fuel_cost = calculate_fuel_cost(&data.0, |distance| distance);
+        let fuel_cost = calculate_fuel_cost(&data.0, &Linear::default()).expect("No crabs").total_cost;
         assert_eq!(fuel_cost, 336120);
     }
 
     #[test]
     fn day_7_part_2_solution() {
         let data: Crabs = data::read_one_line_input_data("./data/d07/data.txt").expect("Invalid input data");
-        // let fuel_cost = calculate_fuel_cost(&data.0, |n| (1..=n).sum());
-        let fuel_cost = calculate_fuel_cost(&data.0, |distance| (distance * (distance + 1)) / 2);
+        let fuel_cost = calculate_fuel_cost(&data.0, &Triangular::default()).expect("No crabs").total_cost;
         assert_eq!(fuel_cost, 96864235);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fast_solvers_match_brute_force() {
+        let example: Crabs = EXAMPLE.parse().expect("Invalid input data");
+        let weighted: Crabs = "16:3,1,2:5,0,4:2,2,7:10,1,2,14:4".parse().expect("Invalid input data");
+        let data: Crabs = data::read_one_line_input_data("./data/d07/data.txt").expect("Invalid input data");
+        for crabs in [example.0, weighted.0, data.0] {
+            let linear = calculate_fuel_cost(&crabs, &Linear::default());
+            assert_eq!(calculate_linear_fuel_cost(&crabs, &Linear::default()), linear);
+            assert_eq!(calculate_convex_fuel_cost(&crabs, &Linear::default()), linear);
+
+            let triangular = calculate_fuel_cost(&crabs, &Triangular::default());
+            assert_eq!(calculate_triangular_fuel_cost(&crabs, &Triangular::default()), triangular);
+            assert_eq!(calculate_convex_fuel_cost(&crabs, &Triangular::default()), triangular);
+        }
+    }
+
+    #[test]
+    fn consider_rightmost_position() {
+        let crabs: Crabs = "0,10,10".parse().expect("Invalid input data");
+        assert_eq!(calculate_fuel_cost(&crabs.0, &Linear::default()).map(|a| a.total_cost), Ok(10));
+        assert_eq!(calculate_convex_fuel_cost(&crabs.0, &Quadratic::default()).map(|a| a.position()), Ok(7));
+    }
+
+    #[test]
+    fn report_ties_and_crab_costs() {
+        let example: Crabs = EXAMPLE.parse().expect("Invalid input data");
+        let alignment = calculate_fuel_cost(&example.0, &Linear::default()).expect("No crabs");
+        assert_eq!(alignment.positions, vec![2]);
+        assert_eq!(alignment.crab_costs, vec![14, 1, 0, 2, 2, 0, 5, 1, 0, 12]);
+
+        let crabs: Crabs = "1,5,3,8".parse().expect("Invalid input data");
+        let alignment = calculate_linear_fuel_cost(&crabs.0, &Linear::default()).expect("No crabs");
+        assert_eq!(alignment.positions, vec![3, 4, 5]);
+        assert_eq!(alignment.total_cost, 9);
+        assert_eq!(calculate_convex_fuel_cost(&crabs.0, &Linear::default()), Ok(alignment));
+    }
+
+    #[test]
+    fn custom_cost_models() {
+        let crabs: Crabs = "0,4:3,10".parse().expect("Invalid input data");
+        let rate = 3;
+        let alignment = calculate_fuel_cost(&crabs.0, &|distance| rate * distance).expect("No crabs");
+        assert_eq!((alignment.positions, alignment.crab_costs), (vec![4], vec![12, 0, 18]));
+
+        // One fuel per step for the first two steps, then five.
+        let piecewise = Piecewise::new(vec![(0, 1), (2, 5)]).expect("Invalid cost");
+        assert_eq!((0..5).map(|d| piecewise.cost(d)).collect::<Vec<_>>(), vec![0, 1, 2, 7, 12]);
+        assert!(piecewise.is_convex());
+        let brute_force = calculate_fuel_cost(&crabs.0, &piecewise);
+        assert_eq!(calculate_convex_fuel_cost(&crabs.0, &piecewise), brute_force);
+        assert!(Piecewise::new(vec![(1, 1)]).is_err());
+        assert!(Piecewise::new(vec![(0, 1), (0, 2)]).is_err());
+    }
+
+    #[test]
+    fn reject_invalid_input() {
+        assert_eq!(calculate_fuel_cost(&[], &Linear::default()), Err(AlignmentError::NoCrabs));
+        assert_eq!(calculate_linear_fuel_cost(&[], &Linear::default()), Err(AlignmentError::NoCrabs));
+        assert_eq!(calculate_triangular_fuel_cost(&[], &Triangular::default()), Err(AlignmentError::NoCrabs));
+        assert_eq!(calculate_convex_fuel_cost(&[], &Linear::default()), Err(AlignmentError::NoCrabs));
+        assert!("1,2:0".parse::<Crabs>().is_err());
+        assert!("1,2:x".parse::<Crabs>().is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use super::{AlignmentError, Crab, FuelCost};
+
+/// Alignment on several targets at once. `targets` are in ascending order and `assignments`
+/// holds, for each crab in input order, the index of the target it moves to.
+#[derive(Debug, PartialEq)]
+pub struct MultiAlignment {
+    pub targets: Vec<i32>,
+    pub assignments: Vec<usize>,
+    pub total_cost: i64,
+}
+
+/// Crabs sharing a position merged into a single heavier crab, sorted by position.
+fn group_by_position(crabs: &[Crab]) -> Vec<Crab> {
+    let mut sorted = crabs.to_vec();
+    sorted.sort_unstable_by_key(|c| c.position);
+    let mut groups: Vec<Crab> = Vec::new();
+    for crab in sorted {
+        match groups.last_mut() {
+            Some(last) if last.position == crab.position => last.weight += crab.weight,
+            _ => groups.push(crab),
+        }
+    }
+    groups
+}
+
+/// Costs of aligning runs of consecutive groups on a single target.
+struct Segments<'a, C: FuelCost> {
+    groups: &'a [Crab],
+    cost: &'a C,
+    /// Prefix sums of `weight`, `weight * position` and `weight * position^2` over the groups.
+    sums: Vec<(i128, i128, i128)>,
+}
+
+impl<'a, C: FuelCost> Segments<'a, C> {
+
+    fn new(groups: &'a [Crab], cost: &'a C) -> Self {
+        let mut sums = Vec::with_capacity(groups.len() + 1);
+        sums.push((0, 0, 0));
+        for group in groups {
+            let (w, p) = (group.weight as i128, group.position as i128);
+            let &(weights, firsts, seconds) = sums.last().expect("Prefix sum");
+            sums.push((weights + w, firsts + w * p, seconds + w * p * p));
+        }
+        Self { groups, cost, sums }
+    }
+
+    fn position(&self, index: usize) -> i64 {
+        self.groups[index].position as i64
+    }
+
+    /// Cost of moving groups `i..=j` to `x`, in O(1) for costs with a quadratic form. `hint`
+    /// is the index of a group close to `x`.
+    fn cost_at(&self, i: usize, j: usize, x: i64, hint: usize) -> i64 {
+        let (a, b) = match self.cost.quadratic_form() {
+            Some(form) => form,
+            None => return self.groups[i..=j].iter().map(|g| g.weight * self.cost.cost((g.position as i64 - x).abs())).sum(),
+        };
+
+        // First group past `x`.
+        let mut split = hint.clamp(i, j + 1);
+        while split > i && self.position(split - 1) > x { split -= 1 }
+        while split <= j && self.position(split) <= x { split += 1 }
+
+        let sum = |from: usize, to: usize| {
+            let (w0, f0, s0) = self.sums[from];
+            let (w1, f1, s1) = self.sums[to];
+            (w1 - w0, f1 - f0, s1 - s0)
+        };
+        let x = x as i128;
+        let (weights, firsts, seconds) = sum(i, j + 1);
+        let squares = seconds - 2 * x * firsts + x * x * weights;
+        let (left_weights, left_firsts, _) = sum(i, split);
+        let (right_weights, right_firsts, _) = sum(split, j + 1);
+        let distances = x * left_weights - left_firsts + right_firsts - x * right_weights;
+        ((a as i128 * squares + b as i128 * distances) / 2) as i64
+    }
+
+    /// Leftmost cheapest target for groups `i..=j`, knowing that group `t` is the cheapest
+    /// of the groups themselves (the leftmost one on ties).
+    fn target(&self, i: usize, j: usize, t: usize) -> (i64, i64) {
+        let f = |x: i64| self.cost_at(i, j, x, t);
+        let at = self.position(t);
+        let (mut low, mut high) = (
+            if t > i { self.position(t - 1) + 1 } else { at },
+            if t < j { self.position(t + 1) - 1 } else { at },
+        );
+        if f(at + 1) >= f(at) && (at == low || f(at - 1) > f(at)) { return (f(at), at) }
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if f(middle + 1) >= f(middle) { high = middle } else { low = middle + 1 }
+        }
+        (f(low), low)
+    }
+
+    /// `table[i][j]`: (cost, target) of aligning groups `i..=j` on a single target. For a given
+    /// `i`, the cheapest group only moves right as `j` grows, as the group added is right of
+    /// every other one; each entry thus takes amortized O(1) cost evaluations to locate it.
+    fn table(&self) -> Vec<Vec<(i64, i32)>> {
+        let m = self.groups.len();
+        let mut table = vec![vec![(0, 0); m]; m];
+        for (i, row) in table.iter_mut().enumerate() {
+            let mut t = i;
+            for (j, cell) in row.iter_mut().enumerate().skip(i) {
+                let at = |k: usize| self.cost_at(i, j, self.position(k), k);
+                while t < j && at(t + 1) < at(t) { t += 1 }
+                let (cost, target) = self.target(i, j, t);
+                *cell = (cost, target as i32);
+            }
+        }
+        table
+    }
+}
+
+/// Aligns the crabs on `k` targets for a non-decreasing convex cost. Every crab moves to its
+/// nearest target, so targets split the sorted positions into contiguous groups: the best split
+/// is found by dynamic programming over the sorted distinct positions. For m distinct positions,
+/// filling the single-target costs takes O(m^2) cost evaluations (O(1) each for linear, triangular
+/// and quadratic costs, O(m) otherwise), plus a binary search between neighboring positions when
+/// the target is not one of them; the split then takes O(k m^2).
+pub fn calculate_multi_target_fuel_cost<C: FuelCost>(crabs: &[Crab], k: usize, cost: &C) -> Result<MultiAlignment, AlignmentError> {
+    let groups = group_by_position(crabs);
+    let m = groups.len();
+    if m == 0 { return Err(AlignmentError::NoCrabs) }
+    if k == 0 || k > m { return Err(AlignmentError::InvalidTargetCount(k)) }
+
+    // segments[i][j]: (cost, target) of aligning groups i..=j on a single target.
+    let segments = Segments::new(&groups, cost).table();
+
+    // best[t][j]: cheapest cost of covering groups 0..j with t targets; start[t][j]: first group of the last segment.
+    let mut best = vec![vec![i64::MAX; m + 1]; k + 1];
+    let mut start = vec![vec![0; m + 1]; k + 1];
+    best[0][0] = 0;
+    for t in 1..=k {
+        for j in t..=m {
+            for i in (t - 1)..j {
+                if best[t - 1][i] == i64::MAX { continue }
+                let candidate = best[t - 1][i] + segments[i][j - 1].0;
+                if candidate < best[t][j] {
+                    best[t][j] = candidate;
+                    start[t][j] = i;
+                }
+            }
+        }
+    }
+
+    let mut targets = Vec::with_capacity(k);
+    let mut first_group = vec![0; k];
+    let mut end = m;
+    for t in (1..=k).rev() {
+        let i = start[t][end];
+        targets.push(segments[i][end - 1].1);
+        first_group[t - 1] = i;
+        end = i;
+    }
+    targets.reverse();
+
+    let assignments = crabs.iter()
+        .map(|crab| {
+            let group = groups.partition_point(|g| g.position < crab.position);
+            first_group.partition_point(|&first| first <= group) - 1
+        })
+        .collect();
+
+    Ok(MultiAlignment { targets, assignments, total_cost: best[k][m] })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::d07::{calculate_convex_fuel_cost, calculate_fuel_cost, Crabs, Linear, Piecewise, Quadratic, Triangular};
+    use crate::data;
+    use super::*;
+
+    const EXAMPLE: &str = "16,1,2,0,4,2,7,1,2,14";
+
+    fn brute_force_two_targets<C: FuelCost>(crabs: &[Crab], cost: &C) -> i64 {
+        let positions = || crabs.iter().map(|c| c.position).min().unwrap_or(0)..=crabs.iter().map(|c| c.position).max().unwrap_or(0);
+        positions().flat_map(|a| positions().map(move |b| (a, b)))
+            .map(|(a, b)| {
+                crabs.iter()
+                    .map(|c| c.weight * i64::min(cost.cost((c.position - a).abs() as i64), cost.cost((c.position - b).abs() as i64)))
+                    .sum()
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn single_target_matches_alignment() {
+        let crabs: Crabs = EXAMPLE.parse().expect("Invalid input data");
+        let alignment = calculate_fuel_cost(&crabs.0, &Triangular::default()).expect("No crabs");
+        let multi = calculate_multi_target_fuel_cost(&crabs.0, 1, &Triangular::default()).expect("No crabs");
+        assert_eq!(multi.total_cost, alignment.total_cost);
+        assert_eq!(multi.targets, vec![alignment.position()]);
+        assert!(multi.assignments.iter().all(|&a| a == 0));
+    }
+
+    #[test]
+    fn two_targets_match_brute_force() {
+        let crabs: Crabs = EXAMPLE.parse().expect("Invalid input data");
+        let weighted: Crabs = "16:3,1,2:5,0,4:2,2,7:10,1,2,14:4".parse().expect("Invalid input data");
+        for crabs in [crabs.0, weighted.0] {
+            assert_eq!(calculate_multi_target_fuel_cost(&crabs, 2, &Linear::default()).map(|a| a.total_cost), Ok(brute_force_two_targets(&crabs, &Linear::default())));
+            assert_eq!(calculate_multi_target_fuel_cost(&crabs, 2, &Triangular::default()).map(|a| a.total_cost), Ok(brute_force_two_targets(&crabs, &Triangular::default())));
+        }
+    }
+
+    #[test]
+    fn assign_crabs_to_targets() {
+        let crabs: Crabs = EXAMPLE.parse().expect("Invalid input data");
+        let multi = calculate_multi_target_fuel_cost(&crabs.0, 3, &Linear::default()).expect("No crabs");
+        assert_eq!(multi.targets, vec![2, 7, 14]);
+        assert_eq!(multi.assignments, vec![2, 0, 0, 0, 0, 0, 1, 0, 0, 2]);
+        assert_eq!(multi.total_cost, 8);
+
+        let distinct = calculate_multi_target_fuel_cost(&crabs.0, 7, &Linear::default()).expect("No crabs");
+        assert_eq!(distinct.total_cost, 0);
+    }
+
+    #[test]
+    fn reject_invalid_target_count() {
+        let crabs: Crabs = EXAMPLE.parse().expect("Invalid input data");
+        assert_eq!(calculate_multi_target_fuel_cost(&crabs.0, 0, &Linear::default()), Err(AlignmentError::InvalidTargetCount(0)));
+        assert_eq!(calculate_multi_target_fuel_cost(&crabs.0, 8, &Linear::default()), Err(AlignmentError::InvalidTargetCount(8)));
+        assert_eq!(calculate_multi_target_fuel_cost(&[], 1, &Linear::default()), Err(AlignmentError::NoCrabs));
+    }
+
+    #[test]
+    fn segments_match_convex_alignment() {
+        let weighted: Crabs = "16:3,1,2:5,0,4:2,2,7:10,1,2,14:4,40:7,23".parse().expect("Invalid input data");
+        let groups = group_by_position(&weighted.0);
+        fn check<C: FuelCost>(groups: &[Crab], cost: &C) {
+            let table = Segments::new(groups, cost).table();
+            for i in 0..groups.len() {
+                for j in i..groups.len() {
+                    let alignment = calculate_convex_fuel_cost(&groups[i..=j], cost).expect("No crabs");
+                    assert_eq!(table[i][j], (alignment.total_cost, alignment.position()));
+                }
+            }
+        }
+        check(&groups, &Linear { rate: 3 });
+        check(&groups, &Triangular { rate: 3 });
+        check(&groups, &Quadratic { coefficient: 2 });
+        check(&groups, &Piecewise::new(vec![(0, 1), (2, 5)]).expect("Invalid cost"));
+        check(&groups, &|d: i64| d * d * d);
+    }
+
+    #[test]
+    fn align_puzzle_crabs_on_three_targets() {
+        let data: Crabs = data::read_one_line_input_data("./data/d07/data.txt").expect("Invalid input data");
+        let multi = calculate_multi_target_fuel_cost(&data.0, 3, &Triangular::default()).expect("No crabs");
+        let single = calculate_fuel_cost(&data.0, &Triangular::default()).expect("No crabs");
+        assert_eq!(multi.targets.len(), 3);
+        assert!(multi.total_cost < single.total_cost);
+    }
+}
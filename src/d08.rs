@@ -1,11 +1,48 @@
 use std::collections::HashSet;
 
+pub mod font;
+pub mod noisy;
+pub mod scrambler;
+pub mod solver;
+
 type Pattern = Vec<char>;
 
 #[derive(Debug)]
 pub struct Entry {
     test: Vec<Pattern>,
     output: Vec<Pattern>,
+    /// 1-based input line, when parsed by `parse_entries`.
+    line: Option<usize>,
+}
+
+/// An entry that could not be used, with the 1-based line (or position in the input) it came from.
+#[derive(Debug, PartialEq)]
+pub struct EntryError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for EntryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+fn parse_patterns(data: &str, expected: usize, name: &str) -> Result<Vec<Pattern>, String> {
+    let mut patterns = Vec::new();
+    for pattern in data.split_ascii_whitespace() {
+        let pattern: Pattern = pattern.chars().collect();
+        if let Some(c) = pattern.iter().find(|c| !('a'..='g').contains(c)) {
+            return Err(format!("Invalid segment '{}' in {} pattern", c, name));
+        }
+        let unique: HashSet<&char> = HashSet::from_iter(pattern.iter());
+        if unique.len() != pattern.len() { return Err(format!("Repeated segment in {} pattern", name)) }
+        patterns.push(pattern);
+    }
+    if patterns.len() != expected {
+        return Err(format!("Expected {} {} patterns, found {}", expected, name, patterns.len()));
+    }
+    Ok(patterns)
 }
 
 impl std::str::FromStr for Entry {
@@ -13,12 +50,31 @@ impl std::str::FromStr for Entry {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut data = s.splitn(2, " | ");
-        let test: Vec<Vec<char>> = data.next().unwrap().split_ascii_whitespace().map(|s| s.chars().collect()).collect();
-        let output: Vec<Vec<char>> = data.next().unwrap().split_ascii_whitespace().map(|s| s.chars().collect()).collect();
-        Ok(Entry { test, output })
+        let test = data.next().ok_or(String::from("Empty entry"))?;
+        let output = data.next().ok_or(String::from("Missing ' | ' separator"))?;
+        let test = parse_patterns(test, 10, "signal")?;
+        let output = parse_patterns(output, 4, "output")?;
+        for (index, pattern) in test.iter().enumerate() {
+            if test[..index].iter().any(|other| are_patterns_equal(pattern, other)) {
+                return Err(String::from("Repeated signal pattern"));
+            }
+        }
+        Ok(Entry { test, output, line: None })
     }
 }
 
+/// Parses every line of the input on its own, so that invalid entries can be skipped or reported.
+pub fn parse_entries(data: &str) -> Vec<Result<Entry, EntryError>> {
+    data.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            line.parse()
+                .map(|entry| Entry { line: Some(index + 1), ..entry })
+                .map_err(|reason| EntryError { line: index + 1, reason })
+        })
+        .collect()
+}
+
 pub fn calculate_known_digits(data: Vec<Entry>) -> usize {
     data.into_iter()
         .map(|e| e.output)
@@ -33,78 +89,35 @@ pub fn calculate_known_digits(data: Vec<Entry>) -> usize {
         .sum()
 }
 
-pub fn parse_entries_and_sum(data: Vec<Entry>) -> usize {
-
-    let mut result = 0;
+impl Entry {
 
-    for entry in data {
-        let mut mappings: Vec<Vec<char>> = vec![Vec::new(); 10];
-        let mut test_data = entry.test;
-        test_data.sort_unstable_by_key(|a| a.len());
-        for test_pattern in test_data {
-            if test_pattern.len() == 2 { mappings[1] = test_pattern }
-            else if test_pattern.len() == 3 { mappings[7] = test_pattern }
-            else if test_pattern.len() == 4 { mappings[4] = test_pattern }
-            else if test_pattern.len() == 5 {
-                if is_three(&test_pattern, &mappings) { mappings[3] = test_pattern }
-                else if is_five(&test_pattern, &mappings) { mappings[5] = test_pattern }
-                else { mappings[2] = test_pattern }
-            }
-            else if test_pattern.len() == 6 {
-                if is_nine(&test_pattern, &mappings) { mappings[9] = test_pattern }
-                else if is_six(&test_pattern, &mappings) { mappings[6] = test_pattern }
-                else { mappings[0] = test_pattern }
-
-            }
-            else if test_pattern.len() == 7 { mappings[8] = test_pattern }
-            else { panic!("You should not be here !") }
-        }
-
-        let output = entry.output.into_iter().fold(String::new(), |acc, s| {
-            format!("{}{}", acc, parse_pattern(s, &mappings).expect("Invalid pattern"))
-        });
-
-        let output: usize = output.parse().expect("Invalid number");
-        result += output;
+    /// Recovers the wiring of the seven-segment display from the signal patterns and decodes the output.
+    pub fn solve(&self) -> Result<solver::Solution, String> {
+        let test: Vec<font::Segments> = self.test.iter().map(|p| font::pattern_to_segments(p)).collect();
+        let output: Vec<font::Segments> = self.output.iter().map(|p| font::pattern_to_segments(p)).collect();
+        solver::solve(&font::DisplayFont::seven_segment(), &test, &output)
     }
-
-    result
 }
 
-fn parse_pattern(pattern: Vec<char>, mappings: &Vec<Vec<char>>) -> Option<String> {
-    for (index, mapping) in mappings.into_iter().enumerate() {
-        if are_patterns_equal(&pattern, mapping) { return Some(index.to_string()) }
+/// Decodes every entry and sums the outputs. Errors report the input line of the entry, or
+/// its position in `data` (starting at 1) for entries not parsed by `parse_entries`.
+pub fn parse_entries_and_sum(data: Vec<Entry>) -> Result<usize, EntryError> {
+    let mut result = 0;
+    for (index, entry) in data.into_iter().enumerate() {
+        let line = entry.line.unwrap_or(index + 1);
+        let error = |reason: String| EntryError { line, reason };
+        let solution = entry.solve().map_err(error)?;
+        result += solution.value().ok_or_else(|| error(String::from("Output is not a number")))?;
     }
-    None
+    Ok(result)
 }
 
-fn are_patterns_equal(a: &Vec<char>, b: &Vec<char>) -> bool {
+fn are_patterns_equal(a: &[char], b: &[char]) -> bool {
     let a: HashSet<&char> = HashSet::from_iter(a.iter());
     let b: HashSet<&char> = HashSet::from_iter(b.iter());
     a == b
 }
 
-fn is_three(input: &Vec<char>, mappings: &Vec<Vec<char>>) -> bool {
-    let x: Vec<_> = mappings[1].iter().filter(|&x| !input.contains(x)).collect();
-    x.is_empty()
-}
-
-fn is_five(input: &Vec<char>, mappings: &Vec<Vec<char>>) -> bool {
-    let x: Vec<_> = mappings[4].iter().filter(|&x| !input.contains(x)).collect();
-    x.len() == 1
-}
-
-fn is_nine(input: &Vec<char>, mappings: &Vec<Vec<char>>) -> bool {
-    let five: Vec<_> =  mappings[5].iter().filter(|&x| !input.contains(x)).collect();
-    let one: Vec<_> = mappings[1].iter().filter(|&x| !input.contains(x)).collect();
-    five.is_empty() && one.is_empty()
-}
-
-fn is_six(input: &Vec<char>, mappings: &Vec<Vec<char>>) -> bool {
-    let x: Vec<_> =  mappings[5].iter().filter(|&x| !input.contains(x)).collect();
-    x.is_empty()
-}
-
 #[cfg(test)]
 mod tests {
 
@@ -122,6 +135,31 @@ mod tests {
     fn day_8_part_2_solution() {
         let data: Vec<Entry> = data::read_input_data("./data/d08/data.txt").expect("Invalid input data");
         let output = parse_entries_and_sum(data);
-        assert_eq!(output, 987553);
+        assert_eq!(output, Ok(987553));
+    }
+
+    #[test]
+    fn report_invalid_entries() {
+        let data = "\
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ah | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb aa | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb ba ab | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb ag";
+        let entries = parse_entries(data);
+        let lines: Vec<usize> = entries.iter().filter_map(|e| e.as_ref().err()).map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 5, 6, 7]);
+        let error = entries[6].as_ref().expect_err("Repeated pattern accepted");
+        assert_eq!(error.to_string(), "line 7: Repeated signal pattern");
+
+        let mut valid: Vec<Entry> = entries.into_iter().filter_map(|e| e.ok()).collect();
+        assert_eq!(parse_entries_and_sum(valid.split_off(1)).map_err(|e| e.line), Err(8));
+        assert_eq!(parse_entries_and_sum(valid), Ok(5353));
+
+        let inconsistent: Entry = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb ag".parse().expect("Invalid entry");
+        assert_eq!(parse_entries_and_sum(vec![inconsistent]).map_err(|e| e.line), Err(1));
     }
 }
\ No newline at end of file
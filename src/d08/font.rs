@@ -0,0 +1,86 @@
+/// Set of segments (or wires) as a bitmask, `a` being the lowest bit.
+pub type Segments = u32;
+
+/// Segments lit for each glyph of a display. Segments are named by letters from `a`,
+/// so a font has at most 26 of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayFont {
+    segments: usize,
+    glyphs: Vec<(char, Segments)>,
+}
+
+impl DisplayFont {
+
+    /// Glyphs must only use the given number of segments and light different sets of them.
+    pub fn new(segments: usize, glyphs: Vec<(char, Segments)>) -> Result<Self, String> {
+        if segments == 0 || segments > 26 { return Err(String::from("Invalid number of segments")) }
+        let all = (1 << segments) - 1;
+        for (index, &(glyph, lit)) in glyphs.iter().enumerate() {
+            if lit & !all != 0 { return Err(format!("Glyph '{}' uses unknown segments", glyph)) }
+            if glyphs[..index].iter().any(|&(other, _)| other == glyph) { return Err(format!("Glyph '{}' defined twice", glyph)) }
+            if let Some(&(other, _)) = glyphs[..index].iter().find(|&&(_, other)| other == lit) {
+                return Err(format!("Glyphs '{}' and '{}' look the same", other, glyph));
+            }
+        }
+        Ok(Self { segments, glyphs })
+    }
+
+    /// Standard digits, segments `a` to `g` going clockwise from the top with `g` in the middle.
+    pub fn seven_segment() -> Self {
+        let glyphs = vec![
+            ('0', "abcefg"), ('1', "cf"), ('2', "acdeg"), ('3', "acdfg"), ('4', "bcdf"),
+            ('5', "abdfg"), ('6', "abdefg"), ('7', "acf"), ('8', "abcdefg"), ('9', "abcdfg"),
+        ];
+        let glyphs = glyphs.into_iter()
+            .map(|(glyph, lit)| (glyph, pattern_to_segments(&lit.chars().collect::<Vec<char>>())))
+            .collect();
+        Self { segments: 7, glyphs }
+    }
+
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    pub fn all_segments(&self) -> Segments {
+        (1 << self.segments) - 1
+    }
+
+    pub fn glyphs(&self) -> &[(char, Segments)] {
+        &self.glyphs
+    }
+
+    pub fn glyph(&self, lit: Segments) -> Option<char> {
+        self.glyphs.iter().find(|&&(_, segments)| segments == lit).map(|&(glyph, _)| glyph)
+    }
+
+    pub fn segments_of(&self, glyph: char) -> Option<Segments> {
+        self.glyphs.iter().find(|&&(other, _)| other == glyph).map(|&(_, segments)| segments)
+    }
+}
+
+/// Bitmask of the segments or wires named in the pattern. Letters past `z` are ignored.
+pub fn pattern_to_segments(pattern: &[char]) -> Segments {
+    pattern.iter()
+        .filter(|c| c.is_ascii_lowercase())
+        .fold(0, |acc, &c| acc | 1 << (c as u8 - b'a'))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn build_fonts() {
+        let font = DisplayFont::seven_segment();
+        assert_eq!(font.glyph(0b0100100), Some('1'));
+        assert_eq!(font.segments_of('8'), Some(0b1111111));
+        assert_eq!(font.glyph(0b0000001), None);
+
+        assert!(DisplayFont::new(3, vec![('x', 0b011), ('y', 0b110)]).is_ok());
+        assert!(DisplayFont::new(3, vec![('x', 0b011), ('y', 0b011)]).is_err());
+        assert!(DisplayFont::new(3, vec![('x', 0b011), ('x', 0b110)]).is_err());
+        assert!(DisplayFont::new(3, vec![('x', 0b1011)]).is_err());
+        assert!(DisplayFont::new(27, vec![]).is_err());
+    }
+}
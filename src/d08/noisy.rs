@@ -0,0 +1,200 @@
+use super::font::{pattern_to_segments, DisplayFont, Segments};
+use super::solver::rewire;
+
+/// A wire whose segment is always lit (`stuck_on`) or never lit, whatever the display shows.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Fault {
+    pub wire: usize,
+    pub segment: usize,
+    pub stuck_on: bool,
+}
+
+/// Most plausible reading of a faulty display. `confidence` is the share of the explanations
+/// with the fewest faults (a wiring together with a glyph for every output) that give `glyphs`.
+#[derive(Debug, PartialEq)]
+pub struct NoisyReading {
+    pub wiring: Vec<usize>,
+    pub glyphs: Vec<char>,
+    pub faults: Vec<Fault>,
+    pub confidence: f64,
+}
+
+/// Splits an entry into signal and output patterns, without requiring them to be distinct
+/// or complete as faulty displays may show the same pattern for different digits.
+pub fn parse_faulty_entry(s: &str) -> Result<(Vec<Segments>, Vec<Segments>), String> {
+    let mut data = s.splitn(2, " | ");
+    let test = data.next().ok_or(String::from("Empty entry"))?;
+    let output = data.next().ok_or(String::from("Missing ' | ' separator"))?;
+    let parse = |data: &str| -> Result<Vec<Segments>, String> {
+        data.split_ascii_whitespace()
+            .map(|pattern| {
+                let pattern: Vec<char> = pattern.chars().collect();
+                if pattern.iter().any(|c| !('a'..='g').contains(c)) { return Err(String::from("Invalid segment")) }
+                Ok(pattern_to_segments(&pattern))
+            })
+            .collect()
+    };
+    Ok((parse(test)?, parse(output)?))
+}
+
+/// A reading with the number of explanations giving it, and the first of them.
+struct Candidate {
+    glyphs: Vec<char>,
+    count: usize,
+    wiring: Vec<usize>,
+    faulty_wires: Vec<usize>,
+}
+
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 { return vec![Vec::new()] }
+    let mut output = Vec::new();
+    for permutation in permutations(n - 1) {
+        for position in 0..n {
+            let mut next = permutation.clone();
+            next.insert(position, n - 1);
+            output.push(next);
+        }
+    }
+    output
+}
+
+fn subsets(items: &[usize], size: usize) -> Vec<Vec<usize>> {
+    if size == 0 { return vec![Vec::new()] }
+    let mut output = Vec::new();
+    for (index, &item) in items.iter().enumerate() {
+        for mut rest in subsets(&items[index + 1..], size - 1) {
+            rest.insert(0, item);
+            output.push(rest);
+        }
+    }
+    output
+}
+
+/// Whether every pattern can be given its own glyph among its candidates (Kuhn's matching).
+fn has_distinct_glyphs(candidates: &[Vec<usize>], glyphs: usize) -> bool {
+    fn augment(pattern: usize, candidates: &[Vec<usize>], owner: &mut Vec<Option<usize>>, seen: &mut Vec<bool>) -> bool {
+        for &glyph in &candidates[pattern] {
+            if seen[glyph] { continue }
+            seen[glyph] = true;
+            if owner[glyph].is_none_or(|other| augment(other, candidates, owner, seen)) {
+                owner[glyph] = Some(pattern);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut owner = vec![None; glyphs];
+    (0..candidates.len()).all(|pattern| augment(pattern, candidates, &mut owner, &mut vec![false; glyphs]))
+}
+
+/// Decodes a display where up to `max_faults` wires may be stuck. A stuck wire shows the same
+/// state in every pattern, so faults are looked for among such wires only, fewest first. For each
+/// set of faults, every wiring is tried and a pattern may show any glyph matching it on the healthy
+/// segments; the test patterns still have to show different glyphs. Fonts are limited to 8 segments.
+pub fn solve_noisy(font: &DisplayFont, test: &[Segments], output: &[Segments], max_faults: usize) -> Result<NoisyReading, String> {
+    let n = font.segments();
+    if n > 8 { return Err(String::from("Fonts with more than 8 segments are not supported")) }
+    let all = font.all_segments();
+    if test.iter().chain(output).any(|&p| p & !all != 0) { return Err(String::from("Pattern uses unknown wires")) }
+
+    let observed: Vec<Segments> = test.iter().chain(output).copied().collect();
+    let always_on = observed.iter().fold(all, |acc, &p| acc & p);
+    let always_off = observed.iter().fold(all, |acc, &p| acc & !p);
+    let constant: Vec<usize> = (0..n).filter(|w| (always_on | always_off) & (1 << w) != 0).collect();
+    let wirings = permutations(n);
+
+    for size in 0..=max_faults.min(constant.len()) {
+        let mut readings: Vec<Candidate> = Vec::new();
+
+        for faulty_wires in subsets(&constant, size) {
+            let faulty = faulty_wires.iter().fold(0, |acc, w| acc | 1 << w);
+            for wiring in &wirings {
+                let healthy = !rewire(faulty, wiring) & all;
+                let matching = |pattern: Segments| -> Vec<usize> {
+                    let lit = rewire(pattern, wiring);
+                    font.glyphs().iter().enumerate()
+                        .filter(|&(_, &(_, segments))| (segments ^ lit) & healthy == 0)
+                        .map(|(index, _)| index)
+                        .collect()
+                };
+
+                let test_candidates: Vec<Vec<usize>> = test.iter().map(|&p| matching(p)).collect();
+                if !has_distinct_glyphs(&test_candidates, font.glyphs().len()) { continue }
+                let output_candidates: Vec<Vec<usize>> = output.iter().map(|&p| matching(p)).collect();
+                if output_candidates.iter().any(|c| c.is_empty()) { continue }
+
+                let mut combinations: Vec<Vec<char>> = vec![Vec::new()];
+                for candidates in &output_candidates {
+                    combinations = combinations.into_iter()
+                        .flat_map(|prefix| candidates.iter().map(move |&g| { let mut next = prefix.clone(); next.push(font.glyphs()[g].0); next }))
+                        .collect();
+                }
+                for glyphs in combinations {
+                    match readings.iter_mut().find(|r| r.glyphs == glyphs) {
+                        Some(reading) => reading.count += 1,
+                        None => readings.push(Candidate { glyphs, count: 1, wiring: wiring.clone(), faulty_wires: faulty_wires.clone() }),
+                    }
+                }
+            }
+        }
+
+        let total: usize = readings.iter().map(|r| r.count).sum();
+        let best = readings.into_iter().fold(None, |best: Option<Candidate>, next| match best {
+            Some(best) if best.count >= next.count => Some(best),
+            _ => Some(next),
+        });
+
+        if let Some(Candidate { glyphs, count, wiring, faulty_wires }) = best {
+            let faults = faulty_wires.into_iter()
+                .map(|wire| Fault { wire, segment: wiring[wire], stuck_on: always_on & (1 << wire) != 0 })
+                .collect();
+            return Ok(NoisyReading { wiring, glyphs, faults, confidence: count as f64 / total as f64 });
+        }
+    }
+
+    Err(format!("No wiring explains the patterns with at most {} faults", max_faults))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const EXAMPLE: &str = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+
+    #[test]
+    fn decode_healthy_display() {
+        let (test, output) = parse_faulty_entry(EXAMPLE).expect("Invalid entry");
+        let reading = solve_noisy(&DisplayFont::seven_segment(), &test, &output, 2).expect("No reading");
+        assert_eq!(reading.glyphs, vec!['5', '3', '5', '3']);
+        assert_eq!(reading.wiring, vec![2, 5, 6, 0, 1, 3, 4]);
+        assert!(reading.faults.is_empty());
+        assert_eq!(reading.confidence, 1.0);
+    }
+
+    #[test]
+    fn decode_display_with_wire_stuck_on() {
+        // Wire d drives the top segment, lighting it on 1 and 4 hides the difference between 1 and 7.
+        let entry = EXAMPLE.replace(" ab ", " abd ").replace("eafb", "eafbd");
+        let (test, output) = parse_faulty_entry(&entry).expect("Invalid entry");
+        let font = DisplayFont::seven_segment();
+        assert!(solve_noisy(&font, &test, &output, 0).is_err());
+
+        let reading = solve_noisy(&font, &test, &output, 1).expect("No reading");
+        assert_eq!(reading.glyphs, vec!['5', '3', '5', '3']);
+        assert_eq!(reading.faults, vec![Fault { wire: 3, segment: 0, stuck_on: true }]);
+        assert_eq!(reading.confidence, 1.0);
+    }
+
+    #[test]
+    fn decode_display_with_wire_stuck_off() {
+        // Wire g drives the lower left segment: 5 and 6 can no longer be told apart.
+        let entry = EXAMPLE.replace('g', "");
+        let (test, output) = parse_faulty_entry(&entry).expect("Invalid entry");
+        let reading = solve_noisy(&DisplayFont::seven_segment(), &test, &output, 2).expect("No reading");
+        assert_eq!(reading.faults, vec![Fault { wire: 6, segment: 4, stuck_on: false }]);
+        assert_eq!(reading.glyphs, vec!['5', '3', '5', '3']);
+        assert_eq!(reading.confidence, 0.25);
+    }
+}
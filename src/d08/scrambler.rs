@@ -0,0 +1,146 @@
+use super::font::{DisplayFont, Segments};
+use super::solver::rewire;
+
+/// Small deterministic generator (SplitMix64), enough to shuffle patterns reproducibly.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Pattern letters of the wires in `wires`, in random order.
+fn write_pattern(wires: Segments, segments: usize, rng: &mut SplitMix64) -> String {
+    let mut letters: Vec<char> = (0..segments).filter(|w| wires & (1 << w) != 0).map(|w| (b'a' + w as u8) as char).collect();
+    rng.shuffle(&mut letters);
+    letters.into_iter().collect()
+}
+
+fn encode_with(font: &DisplayFont, output: &[char], wiring: &[usize], rng: &mut SplitMix64) -> Result<String, String> {
+    let segments = font.segments();
+    let mut inverse = vec![usize::MAX; segments];
+    if wiring.len() != segments { return Err(format!("Expected a wiring of {} wires, found {}", segments, wiring.len())) }
+    for (wire, &segment) in wiring.iter().enumerate() {
+        if segment >= segments || inverse[segment] != usize::MAX { return Err(String::from("Wiring is not a permutation")) }
+        inverse[segment] = wire;
+    }
+
+    let output = output.iter()
+        .map(|&glyph| font.segments_of(glyph).ok_or(format!("Glyph '{}' is not in the font", glyph)))
+        .collect::<Result<Vec<Segments>, String>>()?;
+    let mut test: Vec<Segments> = font.glyphs().iter().map(|&(_, lit)| rewire(lit, &inverse)).collect();
+    rng.shuffle(&mut test);
+
+    let mut write = |patterns: &[Segments]| -> String {
+        patterns.iter().map(|&lit| write_pattern(lit, segments, rng)).collect::<Vec<String>>().join(" ")
+    };
+    let test = write(&test);
+    let output = write(&output.iter().map(|&lit| rewire(lit, &inverse)).collect::<Vec<Segments>>());
+    Ok(format!("{} | {}", test, output))
+}
+
+/// Writes the entry a display wired through `wiring` (`wiring[wire]` being the segment driven by
+/// the wire, as in `solver::Solution`) shows for `output`: every glyph of the font in shuffled order,
+/// then the output glyphs, each pattern with its letters shuffled. The same seed gives the same line.
+pub fn encode_entry(font: &DisplayFont, output: &[char], wiring: &[usize], seed: u64) -> Result<String, String> {
+    encode_with(font, output, wiring, &mut SplitMix64(seed))
+}
+
+/// Entry line of a seven-segment display showing `number` on its four digits, in the format read by `Entry`.
+pub fn encode_number(number: usize, wiring: &[usize], seed: u64) -> Result<String, String> {
+    if number > 9999 { return Err(format!("{} does not fit on four digits", number)) }
+    let digits: Vec<char> = format!("{:04}", number).chars().collect();
+    encode_entry(&DisplayFont::seven_segment(), &digits, wiring, seed)
+}
+
+/// A random entry along with the number and wiring it encodes.
+#[derive(Debug, PartialEq)]
+pub struct GeneratedEntry {
+    pub number: usize,
+    pub wiring: Vec<usize>,
+    pub line: String,
+}
+
+/// Endless stream of random seven-segment entries, reproducible from its seed.
+pub struct EntryGenerator {
+    rng: SplitMix64,
+}
+
+impl EntryGenerator {
+
+    pub fn new(seed: u64) -> Self {
+        Self { rng: SplitMix64(seed) }
+    }
+}
+
+impl Iterator for EntryGenerator {
+    type Item = GeneratedEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let number = self.rng.below(10000);
+        let mut wiring: Vec<usize> = (0..7).collect();
+        self.rng.shuffle(&mut wiring);
+        let digits: Vec<char> = format!("{:04}", number).chars().collect();
+        let line = encode_with(&DisplayFont::seven_segment(), &digits, &wiring, &mut self.rng).ok()?;
+        Some(GeneratedEntry { number, wiring, line })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::d08::Entry;
+    use super::*;
+
+    #[test]
+    fn encode_example_wiring() {
+        let wiring = vec![2, 5, 6, 0, 1, 3, 4];
+        let line = encode_number(5353, &wiring, 42).expect("Invalid wiring");
+        assert_eq!(line, encode_number(5353, &wiring, 42).expect("Invalid wiring"));
+
+        let entry: Entry = line.parse().expect("Invalid entry");
+        let solution = entry.solve().expect("No solution");
+        assert_eq!(solution.wiring, wiring);
+        assert_eq!(solution.value(), Some(5353));
+
+        let (test, output) = line.split_once(" | ").expect("Missing separator");
+        let output: Vec<String> = output.split(' ').map(|p| { let mut p: Vec<char> = p.chars().collect(); p.sort(); p.into_iter().collect() }).collect();
+        assert_eq!(output, vec!["bcdef", "abcdf", "bcdef", "abcdf"]);
+        assert_eq!(test.split(' ').count(), 10);
+    }
+
+    #[test]
+    fn reject_invalid_input() {
+        assert!(encode_number(5353, &[0, 1, 2, 3, 4, 5], 0).is_err());
+        assert!(encode_number(5353, &[0, 1, 2, 3, 4, 5, 5], 0).is_err());
+        assert!(encode_number(10000, &[0, 1, 2, 3, 4, 5, 6], 0).is_err());
+        assert!(encode_entry(&DisplayFont::seven_segment(), &['x'], &[0, 1, 2, 3, 4, 5, 6], 0).is_err());
+    }
+
+    #[test]
+    fn generated_entries_decode() {
+        for generated in EntryGenerator::new(7).take(200) {
+            let entry: Entry = generated.line.parse().expect("Invalid entry");
+            let solution = entry.solve().expect("No solution");
+            assert_eq!(solution.wiring, generated.wiring);
+            assert_eq!(solution.value(), Some(generated.number));
+        }
+        assert_eq!(EntryGenerator::new(7).take(5).collect::<Vec<_>>(), EntryGenerator::new(7).take(5).collect::<Vec<_>>());
+    }
+}
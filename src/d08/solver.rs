@@ -0,0 +1,170 @@
+use super::font::{DisplayFont, Segments};
+
+/// Recovered wiring of a display. `wiring[wire]` is the segment driven by the wire
+/// (both counted from `a`), and `glyphs` holds the decoded output.
+#[derive(Debug, PartialEq)]
+pub struct Solution {
+    pub wiring: Vec<usize>,
+    pub glyphs: Vec<char>,
+    pub unique: bool,
+}
+
+impl Solution {
+
+    /// Output read as a number, when every glyph is a digit.
+    pub fn value(&self) -> Option<usize> {
+        self.glyphs.iter().try_fold(0, |acc, c| Some(acc * 10 + c.to_digit(10)? as usize))
+    }
+}
+
+struct Search<'a> {
+    font: &'a DisplayFont,
+    patterns: Vec<Segments>,
+    outputs: &'a [Segments],
+    used_glyphs: Vec<bool>,
+    found: Vec<(Vec<usize>, Vec<char>)>,
+}
+
+impl Search<'_> {
+
+    /// Assigns the next pattern to every unused glyph with as many segments, narrowing down
+    /// the segments each wire may drive: a wire lit in the pattern drives one of the glyph's
+    /// segments, an unlit wire drives one of the others.
+    fn assign(&mut self, index: usize, candidates: &[Segments]) {
+        if self.found.len() > 1 { return }
+        let pattern = match self.patterns.get(index) {
+            Some(&pattern) => pattern,
+            None => return self.match_wires(0, 0, candidates, &mut vec![0; candidates.len()]),
+        };
+
+        let all = self.font.all_segments();
+        for (glyph, &(_, segments)) in self.font.glyphs().iter().enumerate() {
+            if self.used_glyphs[glyph] || segments.count_ones() != pattern.count_ones() { continue }
+            let narrowed: Vec<Segments> = candidates.iter().enumerate()
+                .map(|(wire, &c)| c & if pattern & (1 << wire) != 0 { segments } else { !segments & all })
+                .collect();
+            if narrowed.iter().all(|&c| c != 0) {
+                self.used_glyphs[glyph] = true;
+                self.assign(index + 1, &narrowed);
+                self.used_glyphs[glyph] = false;
+            }
+        }
+    }
+
+    /// Enumerates the one-to-one wirings allowed by the candidates and keeps those decoding every output.
+    fn match_wires(&mut self, wire: usize, used_segments: Segments, candidates: &[Segments], wiring: &mut Vec<usize>) {
+        if self.found.len() > 1 { return }
+        if wire == candidates.len() {
+            if let Some(glyphs) = decode(self.font, self.outputs, wiring) { self.found.push((wiring.clone(), glyphs)) }
+            return;
+        }
+        for segment in 0..candidates.len() {
+            if candidates[wire] & !used_segments & (1 << segment) == 0 { continue }
+            wiring[wire] = segment;
+            self.match_wires(wire + 1, used_segments | (1 << segment), candidates, wiring);
+        }
+    }
+}
+
+/// Segments lit by the pattern once its wires are routed through `wiring`.
+pub fn rewire(pattern: Segments, wiring: &[usize]) -> Segments {
+    wiring.iter().enumerate()
+        .filter(|&(wire, _)| pattern & (1 << wire) != 0)
+        .fold(0, |acc, (_, &segment)| acc | 1 << segment)
+}
+
+fn decode(font: &DisplayFont, patterns: &[Segments], wiring: &[usize]) -> Option<Vec<char>> {
+    patterns.iter().map(|&pattern| font.glyph(rewire(pattern, wiring))).collect()
+}
+
+/// Finds the wiring under which every test pattern shows a different glyph of the font and every
+/// output pattern shows some glyph. Test patterns may come in any order and need not cover the
+/// whole font; with too few of them the wiring can be ambiguous, which is reported through `unique`.
+pub fn solve(font: &DisplayFont, test: &[Segments], output: &[Segments]) -> Result<Solution, String> {
+    let all = font.all_segments();
+    if test.iter().chain(output).any(|&p| p & !all != 0) { return Err(String::from("Pattern uses unknown wires")) }
+
+    // Patterns matching few glyphs first, so that the search narrows down quickly.
+    let mut patterns = test.to_vec();
+    patterns.sort_by_key(|p| font.glyphs().iter().filter(|(_, g)| g.count_ones() == p.count_ones()).count());
+
+    let used_glyphs = vec![false; font.glyphs().len()];
+    let mut search = Search { font, patterns, outputs: output, used_glyphs, found: Vec::new() };
+    search.assign(0, &vec![all; font.segments()]);
+
+    let unique = search.found.len() == 1;
+    let (wiring, glyphs) = search.found.into_iter().next().ok_or(String::from("No wiring matches the patterns"))?;
+    Ok(Solution { wiring, glyphs, unique })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::d08::font::pattern_to_segments;
+
+    fn segments(patterns: &str) -> Vec<Segments> {
+        patterns.split_ascii_whitespace().map(|p| pattern_to_segments(&p.chars().collect::<Vec<char>>())).collect()
+    }
+
+    /// Some letters of a 14-segment display: `a`-`f` as on seven segments, `g`/`h` the left and
+    /// right halves of the middle bar, `j`/`m` the upper and lower central verticals, `i`/`k`
+    /// the upper and `l`/`n` the lower diagonals.
+    fn fourteen_segment_letters() -> DisplayFont {
+        let glyphs = vec![
+            ('A', "abcefgh"), ('E', "adefg"), ('H', "bcefgh"), ('I', "adjm"), ('J', "bcde"), ('K', "efgkn"),
+            ('L', "def"), ('M', "bcefik"), ('N', "bcefin"), ('O', "abcdef"), ('P', "abefgh"), ('T', "ajm"),
+            ('V', "efkl"), ('X', "ikln"), ('Y', "ikm"), ('Z', "adkl"),
+        ];
+        let glyphs = glyphs.into_iter().map(|(g, s)| (g, segments(s)[0])).collect();
+        DisplayFont::new(14, glyphs).expect("Invalid font")
+    }
+
+    #[test]
+    fn solve_example_entry() {
+        let font = DisplayFont::seven_segment();
+        let test = segments("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab");
+        let output = segments("cdfeb fcadb cdfeb cdbaf");
+        let solution = solve(&font, &test, &output).expect("No solution");
+        // Wires a..g drive segments c, f, g, a, b, d, e.
+        assert_eq!(solution.wiring, vec![2, 5, 6, 0, 1, 3, 4]);
+        assert_eq!(solution.glyphs, vec!['5', '3', '5', '3']);
+        assert_eq!(solution.value(), Some(5353));
+        assert!(solution.unique);
+
+        let mut shuffled = test.clone();
+        shuffled.reverse();
+        assert_eq!(solve(&font, &shuffled, &output), Ok(solution));
+    }
+
+    #[test]
+    fn report_ambiguous_and_impossible_wirings() {
+        let font = DisplayFont::seven_segment();
+        let test = segments("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab");
+        let solution = solve(&font, &segments("dab ab eafb"), &segments("ab dab")).expect("No solution");
+        assert_eq!(solution.glyphs, vec!['1', '7']);
+        assert!(!solution.unique);
+
+        assert!(solve(&font, &segments("ab abc"), &segments("ab")).is_ok());
+        assert!(solve(&font, &segments("ab cd"), &segments("ab")).is_err());
+        assert!(solve(&font, &test, &segments("ag")).is_err());
+        assert!(solve(&font, &test, &segments("ah")).is_err());
+    }
+
+    #[test]
+    fn decode_letters_on_fourteen_segments() {
+        let font = fourteen_segment_letters();
+        let wiring: Vec<usize> = vec![5, 12, 0, 9, 3, 13, 7, 1, 11, 4, 10, 2, 8, 6];
+        let mut inverse = vec![0; wiring.len()];
+        wiring.iter().enumerate().for_each(|(wire, &segment)| inverse[segment] = wire);
+        let scramble = |glyph: char| rewire(font.segments_of(glyph).expect("Unknown glyph"), &inverse);
+
+        let test: Vec<Segments> = font.glyphs().iter().rev().map(|&(glyph, _)| scramble(glyph)).collect();
+        let output: Vec<Segments> = "HELLO".chars().map(scramble).collect();
+        let solution = solve(&font, &test, &output).expect("No solution");
+        assert_eq!(solution.wiring, wiring);
+        assert_eq!(solution.glyphs.iter().collect::<String>(), "HELLO");
+        assert!(solution.unique);
+        assert_eq!(solution.value(), None);
+    }
+}
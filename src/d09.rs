@@ -1,5 +1,11 @@
 use std::collections::VecDeque;
 
+use crate::grid::{Connectivity, Grid, Pos};
+
+pub mod basins;
+pub mod drainage;
+pub mod render;
+
 pub struct Row(Vec<u32>);
 
 impl std::str::FromStr for Row {
@@ -7,7 +13,9 @@ impl std::str::FromStr for Row {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let numbers: Vec<u32> = s.chars().map(|n| n.to_digit(10).expect("Invalid number")).collect();
+        let numbers = s.chars()
+            .map(|n| n.to_digit(10).ok_or(format!("Invalid digit '{}'", n)))
+            .collect::<Result<Vec<u32>, String>>()?;
         Ok(Row(numbers))
     }
 }
@@ -18,74 +26,89 @@ pub struct LowPoint {
     pub item: u32,
 }
 
-pub fn find_low_points(data: &Vec<Vec<u32>>) -> Vec<LowPoint> {
+/// How basins are read from a heightmap. The default matches the puzzle: four neighbors,
+/// `9` as the only wall and low points strictly lower than all their neighbors.
+#[derive(Debug, Clone, Copy)]
+pub struct BasinOptions {
+    pub connectivity: Connectivity,
+    /// Cells at least this high are walls; they never belong to a basin nor are low points.
+    pub wall: u32,
+    /// Whether a flat region with only higher cells around it counts as a low point. It is
+    /// reported once, at its first cell in row-major order.
+    pub plateau_low_points: bool,
+}
+
+impl Default for BasinOptions {
+    fn default() -> Self {
+        Self { connectivity: Connectivity::Four, wall: 9, plateau_low_points: false }
+    }
+}
+
+pub fn find_low_points(data: &Grid<u32>) -> Vec<LowPoint> {
+    find_low_points_with_options(data, &BasinOptions::default())
+}
+
+pub fn find_low_points_with_options(data: &Grid<u32>, options: &BasinOptions) -> Vec<LowPoint> {
     let mut low_points = Vec::new();
+    let mut visited: Grid<bool> = data.map(|_| false);
+
+    for (pos, &item) in data.iter() {
+        if item >= options.wall || visited[pos] { continue }
+        if !options.plateau_low_points {
+            if data.neighbors(pos, options.connectivity).all(|n| data[n] > item) {
+                low_points.push(LowPoint { row_index: pos.row, item_index: pos.col, item });
+            }
+            continue;
+        }
 
-    for (row_index, row) in data.iter().enumerate() {
-        for (item_index, item) in row.iter().enumerate() {
-            let item_up = if row_index != 0 { 
-                data.get(row_index - 1).and_then(|row| row.get(item_index)).filter(|&&x| x > *item).is_some()
-            } else {
-                true
-            };
-            
-            let item_down = if row_index != (data.len() - 1) {
-                data.get(row_index + 1).and_then(|row| row.get(item_index)).filter(|&&x| x > *item).is_some()
-            } else {
-                true
-            };
-            
-            let item_left = if item_index != 0 {
-                data.get(row_index).and_then(|row| row.get(item_index - 1)).filter(|&&x| x > *item).is_some()
-            } else {
-                true
-            };
-
-
-            let item_right = if item_index != (row.len() - 1) {
-                data.get(row_index).and_then(|row| row.get(item_index + 1)).filter(|&&x| x > *item).is_some()
-            } else {
-                true
-            };
-            
-            if item_up && item_down && item_left && item_right { low_points.push(LowPoint {  row_index, item_index, item: *item }) }
+        // Walk the flat region around the cell, checking that everything bordering it is higher.
+        let mut to_be_visited = vec![pos];
+        let mut is_low = true;
+        visited[pos] = true;
+        while let Some(cell) = to_be_visited.pop() {
+            for next in data.neighbors(cell, options.connectivity) {
+                if data[next] < item { is_low = false }
+                if data[next] == item && !visited[next] {
+                    visited[next] = true;
+                    to_be_visited.push(next);
+                }
+            }
         }
+        if is_low { low_points.push(LowPoint { row_index: pos.row, item_index: pos.col, item }) }
     }
     low_points
 }
 
-pub fn multiply_three_biggest_basins(low_points: Vec<(usize, usize)>, data: Vec<Vec<u32>>) -> usize {
-    let mut basins_sizes: Vec<usize> = low_points.into_iter().map(|p| get_basin_size(p, &data)).collect();
+pub fn multiply_three_biggest_basins(low_points: Vec<Pos>, data: &Grid<u32>) -> usize {
+    let mut basins_sizes: Vec<usize> = low_points.into_iter().map(|p| get_basin_size(p, data)).collect();
     basins_sizes.sort_by(|&a, &b| a.cmp(&b));
     basins_sizes.into_iter().rev().take(3).fold(1, |acc, n| acc * n)
 }
 
-pub fn get_basin_size(low_point: (usize, usize), data: &Vec<Vec<u32>>) -> usize {
-    let mut to_be_visited: VecDeque<(usize, usize)> = VecDeque::new();
-    let mut visited: Vec<(usize, usize)> = Vec::new();
-
-    to_be_visited.push_back(low_point);
+/// Size of the basin around the given point. Use `basins::BasinMap` to size every basin at once.
+pub fn get_basin_size(low_point: Pos, data: &Grid<u32>) -> usize {
+    get_basin_size_with_options(low_point, data, &BasinOptions::default())
+}
 
-    while let Some((x, y)) = to_be_visited.pop_front() {
-        visited.push((x, y));
-        
-        if (x > 0) && data[x-1][y] != 9 && !visited.contains(&(x - 1, y)) { 
-            if !to_be_visited.contains(&(x - 1, y)) { to_be_visited.push_back((x - 1, y)) } 
-        }
+pub fn get_basin_size_with_options(low_point: Pos, data: &Grid<u32>, options: &BasinOptions) -> usize {
+    let mut to_be_visited: VecDeque<Pos> = VecDeque::new();
+    let mut visited: Grid<bool> = data.map(|_| false);
+    let mut size = 0;
 
-        if (x < data.len() - 1) && data[x+1][y] != 9 && !visited.contains(&(x + 1, y)) {
-            if !to_be_visited.contains(&(x + 1, y)) { to_be_visited.push_back((x + 1, y)) }
-        }
+    visited[low_point] = true;
+    to_be_visited.push_back(low_point);
 
-        if (y > 0) && data[x][y-1] != 9 && !visited.contains(&(x, y - 1)) {
-            if !to_be_visited.contains(&(x, y - 1)) { to_be_visited.push_back((x, y - 1)) }
-        }
+    while let Some(pos) = to_be_visited.pop_front() {
+        size += 1;
 
-        if (y < data[x].len() - 1) && data[x][y+1] != 9 && !visited.contains(&(x, y + 1)) {
-            if !to_be_visited.contains(&(x, y + 1)) { to_be_visited.push_back((x, y + 1)) }
+        for next in data.neighbors(pos, options.connectivity) {
+            if data[next] < options.wall && !visited[next] {
+                visited[next] = true;
+                to_be_visited.push_back(next);
+            }
         }
     }
-    visited.len()
+    size
 }
 
 
@@ -98,18 +121,44 @@ mod tests {
     #[test]
     fn day_9_part_1_solution() {
         let data: Vec<Row> = data::read_input_data("./data/d09/data.txt").expect("Invalid input data");
-        let data: Vec<Vec<u32>> = data.into_iter().map(|r| r.0).collect();
+        let data = Grid::from_rows(data.into_iter().map(|r| r.0).collect()).expect("Invalid input data");
         let low_points = find_low_points(&data);
         let output = low_points.into_iter().fold(0, |acc, p| acc + (p.item + 1));
         assert_eq!(output, 558);
     }
 
+    #[test]
+    fn reject_invalid_rows() {
+        assert!("2199943210".parse::<Row>().is_ok());
+        assert_eq!("21a9".parse::<Row>().err(), Some(String::from("Invalid digit 'a'")));
+    }
+
+    #[test]
+    fn find_low_points_with_custom_options() {
+        let positions = |data: &str, options: &BasinOptions| -> Vec<(usize, usize)> {
+            let data = Grid::parse_digits(data).expect("Invalid heightmap");
+            find_low_points_with_options(&data, options).into_iter().map(|p| (p.row_index, p.item_index)).collect()
+        };
+        let plateaus = BasinOptions { plateau_low_points: true, ..BasinOptions::default() };
+        let eight = BasinOptions { connectivity: Connectivity::Eight, ..BasinOptions::default() };
+
+        let flat = "3113\n3333\n2234";
+        assert!(positions(flat, &BasinOptions::default()).is_empty());
+        assert_eq!(positions(flat, &plateaus), vec![(0, 1), (2, 0)]);
+        assert_eq!(positions(flat, &BasinOptions { wall: 2, ..plateaus }), vec![(0, 1)]);
+
+        let diagonal = "31\n13";
+        assert_eq!(positions(diagonal, &BasinOptions::default()), vec![(0, 1), (1, 0)]);
+        assert!(positions(diagonal, &eight).is_empty());
+        assert_eq!(positions(diagonal, &BasinOptions { plateau_low_points: true, ..eight }), vec![(0, 1)]);
+    }
+
     #[test]
     fn day_9_part_2_solution() {
         let data: Vec<Row> = data::read_input_data("./data/d09/data.txt").expect("Invalid input data");
-        let data: Vec<Vec<u32>> = data.into_iter().map(|r| r.0).collect();
-        let low_points = find_low_points(&data).into_iter().map(|p| (p.row_index, p.item_index)).collect();
-        let three_biggest_basins = multiply_three_biggest_basins(low_points, data);
+        let data = Grid::from_rows(data.into_iter().map(|r| r.0).collect()).expect("Invalid input data");
+        let low_points = find_low_points(&data).into_iter().map(|p| Pos::new(p.row_index, p.item_index)).collect();
+        let three_biggest_basins = multiply_three_biggest_basins(low_points, &data);
         assert_eq!(three_biggest_basins, 882942);
     }
 }
\ No newline at end of file
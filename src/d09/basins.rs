@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+
+use crate::grid::{Grid, Pos};
+
+use super::BasinOptions;
+
+/// A region of cells lower than the walls. `min` and `max` are the corners of its bounding box.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Basin {
+    pub size: usize,
+    pub low_point: Pos,
+    pub min: Pos,
+    pub max: Pos,
+}
+
+/// Every cell of the heightmap labeled with the basin it belongs to, walls being left unlabeled.
+#[derive(Debug)]
+pub struct BasinMap {
+    labels: Grid<Option<usize>>,
+    basins: Vec<Basin>,
+}
+
+impl BasinMap {
+
+    /// Labels the whole heightmap in a single flood fill, each cell being visited once. Basins are
+    /// numbered in the order their first cell comes in row-major order; the low point of a basin is
+    /// its lowest cell, the first one in row-major order on ties.
+    pub fn new(heights: &Grid<u32>) -> Self {
+        Self::with_options(heights, &BasinOptions::default())
+    }
+
+    pub fn with_options(heights: &Grid<u32>, options: &BasinOptions) -> Self {
+        let mut labels: Grid<Option<usize>> = heights.map(|_| None);
+        let mut basins: Vec<Basin> = Vec::new();
+        let mut queue: VecDeque<Pos> = VecDeque::new();
+
+        for start in heights.positions() {
+            if heights[start] >= options.wall || labels[start].is_some() { continue }
+            let label = basins.len();
+            let mut basin = Basin { size: 0, low_point: start, min: start, max: start };
+            labels[start] = Some(label);
+            queue.push_back(start);
+
+            while let Some(pos) = queue.pop_front() {
+                basin.size += 1;
+                if heights[pos] < heights[basin.low_point] || (heights[pos] == heights[basin.low_point] && pos < basin.low_point) {
+                    basin.low_point = pos;
+                }
+                basin.min = Pos::new(basin.min.row.min(pos.row), basin.min.col.min(pos.col));
+                basin.max = Pos::new(basin.max.row.max(pos.row), basin.max.col.max(pos.col));
+
+                for next in heights.neighbors(pos, options.connectivity) {
+                    if heights[next] < options.wall && labels[next].is_none() {
+                        labels[next] = Some(label);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            basins.push(basin);
+        }
+
+        Self { labels, basins }
+    }
+
+    pub fn label(&self, pos: Pos) -> Option<usize> {
+        self.labels.get(pos).copied().flatten()
+    }
+
+    pub fn labels(&self) -> &Grid<Option<usize>> {
+        &self.labels
+    }
+
+    pub fn basins(&self) -> &[Basin] {
+        &self.basins
+    }
+
+    /// The `k` largest basins, largest first; ties keep the labeling order.
+    pub fn largest(&self, k: usize) -> Vec<&Basin> {
+        let mut basins: Vec<&Basin> = self.basins.iter().collect();
+        basins.sort_by_key(|b| std::cmp::Reverse(b.size));
+        basins.truncate(k);
+        basins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::grid::Connectivity;
+    use super::*;
+
+    const EXAMPLE: &str = "\
+2199943210
+3987894921
+9856789892
+8767896789
+9899965678";
+
+    #[test]
+    fn label_example_basins() {
+        let heights = Grid::parse_digits(EXAMPLE).expect("Invalid heightmap");
+        let map = BasinMap::new(&heights);
+        let sizes: Vec<usize> = map.basins().iter().map(|b| b.size).collect();
+        assert_eq!(sizes, vec![3, 9, 14, 9]);
+        assert_eq!(map.basins()[1], Basin { size: 9, low_point: Pos::new(0, 9), min: Pos::new(0, 5), max: Pos::new(2, 9) });
+        assert_eq!(map.basins()[2].low_point, Pos::new(2, 2));
+        assert_eq!(map.label(Pos::new(1, 0)), Some(0));
+        assert_eq!(map.label(Pos::new(0, 2)), None);
+        assert_eq!(map.label(Pos::new(5, 0)), None);
+
+        let largest: usize = map.largest(3).iter().map(|b| b.size).product();
+        assert_eq!(largest, 1134);
+    }
+
+    #[test]
+    fn label_with_options() {
+        let heights = Grid::parse_digits("181\n818\n181").expect("Invalid heightmap");
+        let sizes = |options: &BasinOptions| -> Vec<usize> {
+            BasinMap::with_options(&heights, options).basins().iter().map(|b| b.size).collect()
+        };
+        assert_eq!(sizes(&BasinOptions::default()), vec![9]);
+
+        let walls = BasinOptions { wall: 7, ..BasinOptions::default() };
+        assert_eq!(sizes(&walls), vec![1, 1, 1, 1, 1]);
+        assert_eq!(sizes(&BasinOptions { connectivity: Connectivity::Eight, ..walls }), vec![5]);
+    }
+
+    #[test]
+    fn label_whole_heightmap() {
+        let data = std::fs::read_to_string("./data/d09/data.txt").expect("Invalid input data");
+        let heights = Grid::parse_digits(&data).expect("Invalid heightmap");
+        let map = BasinMap::new(&heights);
+        let largest: usize = map.largest(3).iter().map(|b| b.size).product();
+        assert_eq!(largest, 882942);
+
+        let walls = heights.iter().filter(|&(_, &h)| h == 9).count();
+        assert_eq!(map.basins().iter().map(|b| b.size).sum::<usize>() + walls, heights.width() * heights.height());
+        assert!(map.labels().iter().all(|(pos, label)| label.is_none() == (heights[pos] == 9)));
+    }
+}
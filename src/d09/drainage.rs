@@ -0,0 +1,150 @@
+use crate::grid::{Grid, Pos};
+
+/// What to do when a cell has several lowest neighbors at the same height.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TieBreak {
+    /// Drain to the first of them in row-major order.
+    First,
+    /// Leave the cell unassigned when they end up in different sinks.
+    Divide,
+}
+
+/// Why a cell does not end up in a single sink.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Unassigned {
+    /// No lower neighbor but one at the same height: the water has nowhere definite to go.
+    Plateau,
+    /// Lowest neighbors at the same height leading to different sinks.
+    Divide,
+    /// Drains into an unassigned cell.
+    Downstream,
+}
+
+/// Where the water falling on each cell of a heightmap ends up.
+#[derive(Debug)]
+pub struct Drainage {
+    downstream: Grid<Option<Pos>>,
+    sinks: Grid<Result<Pos, Unassigned>>,
+    accumulation: Grid<usize>,
+}
+
+impl Drainage {
+
+    /// Lets every cell drain to its lowest strictly lower neighbor. Cells without one are sinks,
+    /// unless a neighbor shares their height. Cells are solved from the lowest up, so the sink of
+    /// the neighbor a cell drains to is always known already.
+    pub fn new(heights: &Grid<u32>, tie_break: TieBreak) -> Self {
+        let mut order: Vec<Pos> = heights.positions().collect();
+        order.sort_by_key(|&pos| heights[pos]);
+
+        let mut downstream: Grid<Option<Pos>> = heights.map(|_| None);
+        let mut sinks: Grid<Result<Pos, Unassigned>> = heights.map(|_| Err(Unassigned::Plateau));
+        for &pos in &order {
+            let height = heights[pos];
+            let lowest = heights.neighbors4(pos).map(|n| heights[n]).filter(|&h| h < height).min();
+            let lowest = match lowest {
+                Some(lowest) => lowest,
+                None => {
+                    let is_flat = heights.neighbors4(pos).any(|n| heights[n] == height);
+                    sinks[pos] = if is_flat { Err(Unassigned::Plateau) } else { Ok(pos) };
+                    continue;
+                }
+            };
+
+            let tied: Vec<Pos> = heights.neighbors4(pos).filter(|&n| heights[n] == lowest).collect();
+            if tie_break == TieBreak::Divide && tied.iter().any(|&n| sinks[n] != sinks[tied[0]]) {
+                sinks[pos] = Err(Unassigned::Divide);
+                continue;
+            }
+            downstream[pos] = Some(tied[0]);
+            sinks[pos] = sinks[tied[0]].map_err(|_| Unassigned::Downstream);
+        }
+
+        let mut accumulation: Grid<usize> = heights.map(|_| 1);
+        for &pos in order.iter().rev() {
+            if let Some(next) = downstream[pos] { accumulation[next] += accumulation[pos] }
+        }
+
+        Self { downstream, sinks, accumulation }
+    }
+
+    /// Neighbor the cell drains to, `None` for sinks and cells where the water stops.
+    pub fn downstream(&self, pos: Pos) -> Option<Pos> {
+        self.downstream.get(pos).copied().flatten()
+    }
+
+    pub fn sink(&self, pos: Pos) -> Option<Pos> {
+        self.sinks.get(pos).and_then(|sink| sink.ok())
+    }
+
+    pub fn sinks(&self) -> &Grid<Result<Pos, Unassigned>> {
+        &self.sinks
+    }
+
+    /// Number of cells draining through each cell, the cell itself included.
+    pub fn accumulation(&self) -> &Grid<usize> {
+        &self.accumulation
+    }
+
+    /// Cells belonging to no single sink, in row-major order.
+    pub fn unassigned(&self) -> Vec<(Pos, Unassigned)> {
+        self.sinks.iter().filter_map(|(pos, sink)| sink.err().map(|reason| (pos, reason))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+2199943210
+3987894921
+9856789892
+8767896789
+9899965678";
+
+    #[test]
+    fn drain_example() {
+        let heights = Grid::parse_digits(EXAMPLE).expect("Invalid heightmap");
+        let drainage = Drainage::new(&heights, TieBreak::First);
+        let mut sinks: Vec<Pos> = drainage.sinks().iter().filter_map(|(pos, &sink)| sink.ok().filter(|&s| s == pos)).collect();
+        sinks.sort();
+        assert_eq!(sinks, vec![Pos::new(0, 1), Pos::new(0, 9), Pos::new(2, 2), Pos::new(4, 6)]);
+
+        let drained: usize = sinks.iter().map(|&s| drainage.accumulation()[s]).sum();
+        assert_eq!(drained + drainage.unassigned().len(), 50);
+        assert_eq!(drainage.sink(Pos::new(1, 0)), Some(Pos::new(0, 1)));
+        assert_eq!(drainage.downstream(Pos::new(0, 0)), Some(Pos::new(0, 1)));
+        assert_eq!(drainage.downstream(Pos::new(0, 1)), None);
+    }
+
+    #[test]
+    fn split_divides() {
+        let heights = Grid::parse_digits("010").expect("Invalid heightmap");
+        let first = Drainage::new(&heights, TieBreak::First);
+        assert_eq!(first.sink(Pos::new(0, 1)), Some(Pos::new(0, 0)));
+        assert_eq!(first.accumulation().row(0), Some(&[2, 1, 1][..]));
+        assert!(first.unassigned().is_empty());
+
+        let divide = Drainage::new(&heights, TieBreak::Divide);
+        assert_eq!(divide.sink(Pos::new(0, 1)), None);
+        assert_eq!(divide.accumulation().row(0), Some(&[1, 1, 1][..]));
+        assert_eq!(divide.unassigned(), vec![(Pos::new(0, 1), Unassigned::Divide)]);
+    }
+
+    #[test]
+    fn report_plateaus() {
+        let heights = Grid::parse_digits("211\n333").expect("Invalid heightmap");
+        let drainage = Drainage::new(&heights, TieBreak::First);
+        assert_eq!(drainage.unassigned(), vec![
+            (Pos::new(0, 0), Unassigned::Downstream),
+            (Pos::new(0, 1), Unassigned::Plateau),
+            (Pos::new(0, 2), Unassigned::Plateau),
+            (Pos::new(1, 0), Unassigned::Downstream),
+            (Pos::new(1, 1), Unassigned::Downstream),
+            (Pos::new(1, 2), Unassigned::Downstream),
+        ]);
+        assert_eq!(drainage.accumulation()[Pos::new(0, 1)], 4);
+    }
+}
@@ -0,0 +1,194 @@
+use std::io::{Error, ErrorKind, Write};
+
+use crate::grid::{Grid, Pos};
+
+use super::basins::BasinMap;
+use super::{find_low_points_with_options, BasinOptions};
+
+type Rgb = (u8, u8, u8);
+
+const LOW_POINT: Rgb = (255, 0, 0);
+const WALL: Rgb = (0, 0, 0);
+
+/// How cells which are neither walls nor low points are colored.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Shading {
+    /// Gray getting lighter with height.
+    Grayscale,
+    /// Color of the basin, getting darker with height.
+    Basins,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Cell {
+    Wall,
+    LowPoint,
+    Basin(usize, u32),
+}
+
+/// Color of the basin with the given label. Hues step by the golden ratio so that every label
+/// gets its own color and consecutive labels are far apart; hues within 30 degrees of red are
+/// left out, red being kept for low points.
+fn basin_color(label: usize) -> Rgb {
+    const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+    let hue = 30.0 + 300.0 * (label as f64 * GOLDEN_RATIO_CONJUGATE).fract();
+    hsv_to_rgb(hue, 0.7, 0.95)
+}
+
+/// Converts a hue in degrees, a saturation and a value in `0..=1` to RGB.
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> Rgb {
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+fn classify(heights: &Grid<u32>, options: &BasinOptions) -> Grid<Cell> {
+    let map = BasinMap::with_options(heights, options);
+    let mut cells: Grid<Cell> = heights.map(|_| Cell::Wall);
+    for (pos, cell) in cells.iter_mut() {
+        if let Some(label) = map.label(pos) { *cell = Cell::Basin(label, heights[pos]) }
+    }
+    for low_point in find_low_points_with_options(heights, options) {
+        cells[Pos::new(low_point.row_index, low_point.item_index)] = Cell::LowPoint;
+    }
+    cells
+}
+
+/// Color of a cell, heights being shaded on a scale from 0 to `top`.
+fn color(cell: Cell, shading: Shading, top: u32) -> Rgb {
+    let top = top.max(1) as u64;
+    match cell {
+        Cell::Wall => WALL,
+        Cell::LowPoint => LOW_POINT,
+        Cell::Basin(_, height) if shading == Shading::Grayscale => {
+            let gray = (255 * (height as u64).min(top) / top) as u8;
+            (gray, gray, gray)
+        }
+        Cell::Basin(label, height) => {
+            let (r, g, b) = basin_color(label);
+            let shade = |c: u8| (c as u64 * (2 * top - (height as u64).min(top)) / (2 * top)) as u8;
+            (shade(r), shade(g), shade(b))
+        }
+    }
+}
+
+/// Top of the shading scale: the wall height, or just above the highest cell when walls are higher.
+fn shading_top(heights: &Grid<u32>, options: &BasinOptions) -> u32 {
+    let highest = heights.iter().map(|(_, &h)| h).max().unwrap_or(0);
+    options.wall.min(highest.saturating_add(1))
+}
+
+/// Writes the heightmap as a binary (P6) PPM image, one pixel per cell: walls in black,
+/// low points in red and every other cell shaded from its height.
+pub fn write_ppm<W: Write>(heights: &Grid<u32>, options: &BasinOptions, shading: Shading, writer: &mut W) -> Result<(), Error> {
+    if heights.is_empty() { return Err(Error::new(ErrorKind::InvalidInput, "Empty heightmap")) }
+
+    let top = shading_top(heights, options);
+    let pixels: Vec<u8> = classify(heights, options).iter()
+        .flat_map(|(_, &cell)| { let (r, g, b) = color(cell, shading, top); [r, g, b] })
+        .collect();
+
+    write!(writer, "P6\n{} {}\n255\n", heights.width(), heights.height())?;
+    writer.write_all(&pixels)
+}
+
+/// Renders the heightmap as digits for a terminal: walls dimmed, low points in bold red
+/// and basins in their (unshaded) colors. Every line ends by resetting the style.
+pub fn render_ansi(heights: &Grid<u32>, options: &BasinOptions) -> String {
+    let cells = classify(heights, options);
+    let mut output = String::new();
+    for (row, heights) in cells.rows().zip(heights.rows()) {
+        for (&cell, height) in row.iter().zip(heights) {
+            let style = match cell {
+                Cell::Wall => String::from("2"),
+                Cell::LowPoint => String::from("1;31"),
+                Cell::Basin(label, _) => {
+                    let (r, g, b) = basin_color(label);
+                    format!("38;2;{};{};{}", r, g, b)
+                }
+            };
+            output.push_str(&format!("\x1b[0;{}m{}", style, height));
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn heights() -> Grid<u32> {
+        Grid::parse_digits("129\n999").expect("Invalid heightmap")
+    }
+
+    #[test]
+    fn write_small_ppm() {
+        let mut output = Vec::new();
+        write_ppm(&heights(), &BasinOptions::default(), Shading::Basins, &mut output).expect("Unable to write image");
+        let mut expected = b"P6\n3 2\n255\n".to_vec();
+        expected.extend_from_slice(&[255, 0, 0, 215, 139, 64]);
+        expected.extend_from_slice(&[0; 12]);
+        assert_eq!(output, expected);
+
+        let mut output = Vec::new();
+        write_ppm(&heights(), &BasinOptions::default(), Shading::Grayscale, &mut output).expect("Unable to write image");
+        assert_eq!(&output[11..17], &[255, 0, 0, 56, 56, 56]);
+    }
+
+    #[test]
+    fn write_ppm_without_walls() {
+        for shading in [Shading::Basins, Shading::Grayscale] {
+            let mut unbounded = Vec::new();
+            let options = BasinOptions { wall: u32::MAX, ..BasinOptions::default() };
+            write_ppm(&heights(), &options, shading, &mut unbounded).expect("Unable to write image");
+            let mut bounded = Vec::new();
+            let options = BasinOptions { wall: 10, ..BasinOptions::default() };
+            write_ppm(&heights(), &options, shading, &mut bounded).expect("Unable to write image");
+            assert_eq!(unbounded, bounded);
+        }
+    }
+
+    #[test]
+    fn write_empty_ppm() {
+        let mut output = Vec::new();
+        assert!(write_ppm(&Grid::default(), &BasinOptions::default(), Shading::Basins, &mut output).is_err());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn render_small_ansi() {
+        let expected = "\x1b[0;1;31m1\x1b[0;38;2;242;157;73m2\x1b[0;2m9\x1b[0m\n\x1b[0;2m9\x1b[0;2m9\x1b[0;2m9\x1b[0m\n";
+        assert_eq!(render_ansi(&heights(), &BasinOptions::default()), expected);
+    }
+
+    #[test]
+    fn color_basins_apart() {
+        // Two basins on either side of a wall, their cells of height 2 being drawn the same shade.
+        let mut output = Vec::new();
+        let heights = Grid::parse_digits("21912").expect("Invalid heightmap");
+        write_ppm(&heights, &BasinOptions::default(), Shading::Basins, &mut output).expect("Unable to write image");
+        let pixels = &output[11..];
+        assert_ne!(&pixels[0..3], &pixels[12..15]);
+
+        let data = std::fs::read_to_string("./data/d09/data.txt").expect("Invalid input data");
+        let heights = Grid::parse_digits(&data).expect("Invalid heightmap");
+        let basins = BasinMap::new(&heights).basins().len();
+        let mut colors: Vec<Rgb> = (0..basins).map(basin_color).collect();
+        assert!(!colors.contains(&LOW_POINT));
+        colors.sort();
+        colors.dedup();
+        assert_eq!(colors.len(), basins);
+    }
+}
@@ -1,4 +1,11 @@
-use std::collections::VecDeque;
+use brackets::{BracketSet, Token};
+use crate::bigint::BigUint;
+
+pub mod brackets;
+pub mod diagnostics;
+pub mod repair;
+pub mod stream;
+pub mod tree;
 
 pub struct Row(Vec<char>);
 
@@ -12,128 +19,132 @@ impl std::str::FromStr for Row {
     }
 }
 
-fn char_to_score(c: char) -> usize {
-    match c {
-        ')' => 3,
-        ']' => 57,
-        '}' => 1197,
-        '>' => 25137,
-        _ => 0
+/// Brackets of a line with no mismatched closer.
+#[derive(Debug, PartialEq, Eq)]
+struct OpenBrackets {
+    /// Pair indices of the brackets left open, innermost last.
+    pairs: Vec<usize>,
+    /// Number of closers found with nothing open, which are skipped.
+    unopened: usize,
+}
+
+impl OpenBrackets {
+
+    fn is_balanced(&self) -> bool {
+        self.pairs.is_empty() && self.unopened == 0
     }
 }
 
-fn find_corrupted_char(chars: &Vec<char>) -> Option<char> {
-    let mut expected_closing_chars: VecDeque<char> = VecDeque::new();
+/// Pair index of the first closer not matching the last opened bracket.
+fn find_corrupted_char(chars: &[char], brackets: &BracketSet) -> Option<usize> {
+    analyze_chars(chars, brackets).err()
+}
 
-    for &c in chars {
-        match c {
-            '(' => expected_closing_chars.push_back(')'),
-            '[' => expected_closing_chars.push_back(']'),
-            '{' => expected_closing_chars.push_back('}'),
-            '<' => expected_closing_chars.push_back('>'),
-            ')' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != ')' { return Some(')') };
-                }
-            },
-            ']' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != ']' { return Some(']') };
-                }
-            },
-            '}' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != '}' { return Some('}') };
-                }
-            }
-            '>' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != '>' { return Some('>') };
+/// Brackets left open, or the pair of the first closer not matching the last opened bracket.
+fn analyze_chars(chars: &[char], brackets: &BracketSet) -> Result<OpenBrackets, usize> {
+    let mut open_pairs: Vec<usize> = Vec::new();
+    let mut unopened = 0;
+
+    for lexeme in brackets.tokenize(chars) {
+        match lexeme.token {
+            Token::Open(pair) => open_pairs.push(pair),
+            Token::Close(pair) => {
+                match open_pairs.pop() {
+                    Some(e) if e != pair => return Err(pair),
+                    Some(_) => (),
+                    None => unopened += 1,
                 }
             },
-            _ => ()
+            Token::Other(_) => ()
         }
     }
 
-    None
+    Ok(OpenBrackets { pairs: open_pairs, unopened })
 }
 
-fn analyze_chars(chars: &Vec<char>) -> Result<VecDeque<char>, char> {
-    let mut expected_closing_chars: VecDeque<char> = VecDeque::new();
+#[derive(Debug, PartialEq)]
+pub enum ScoreError {
+    NoIncompleteLines,
+    Overflow,
+}
 
-    for &c in chars {
-        match c {
-            '(' => expected_closing_chars.push_back(')'),
-            '[' => expected_closing_chars.push_back(']'),
-            '{' => expected_closing_chars.push_back('}'),
-            '<' => expected_closing_chars.push_back('>'),
-            ')' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != ')' { return Err(')') };
-                }
-            },
-            ']' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != ']' { return Err(']') };
-                }
-            },
-            '}' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != '}' { return Err('}') };
-                }
-            }
-            '>' => {
-                if let Some(e) = expected_closing_chars.pop_back() {
-                    if e != '>' { return Err('>') };
-                }
-            },
-            _ => ()
+impl std::fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ScoreError::NoIncompleteLines => write!(f, "No incomplete lines to score"),
+            ScoreError::Overflow => write!(f, "Completion score does not fit in 128 bits"),
         }
     }
+}
 
-    Ok(expected_closing_chars)
+pub fn find_middle_completions_score(data: Vec<Vec<char>>) -> Result<u128, ScoreError> {
+    find_middle_completions_score_with_brackets(data, &BracketSet::default())
 }
 
-pub fn find_middle_completions_score(data: Vec<Vec<char>>) -> usize {
-    let mut scores = Vec::new();
-    for row in data {
-        match analyze_chars(&row) {
-            Ok(xs) => {
-                let score = calculate_score(xs);
-                scores.push(score);
-            },
-            Err(_) => (),
-        }
-    }
+pub fn find_middle_completions_score_with_brackets(data: Vec<Vec<char>>, brackets: &BracketSet) -> Result<u128, ScoreError> {
+    let scores = completion_scores(data, brackets, |open| calculate_score(open, brackets).ok_or(ScoreError::Overflow))?;
+    middle_score(scores)
+}
+
+/// Same as `find_middle_completions_score_with_brackets`, without any bound on the scores.
+pub fn find_middle_completions_big_score_with_brackets(data: Vec<Vec<char>>, brackets: &BracketSet) -> Result<BigUint, ScoreError> {
+    let scores = completion_scores(data, brackets, |open| Ok(calculate_big_score(open, brackets)))?;
+    middle_score(scores)
+}
+
+/// Scores of the incomplete lines, corrupted and complete ones being skipped.
+fn completion_scores<T, F>(data: Vec<Vec<char>>, brackets: &BracketSet, score: F) -> Result<Vec<T>, ScoreError>
+where
+    F: Fn(Vec<usize>) -> Result<T, ScoreError>,
+{
+    data.into_iter()
+        .filter_map(|row| analyze_chars(&row, brackets).ok())
+        .filter(|open| !open.pairs.is_empty())
+        .map(|open| score(open.pairs))
+        .collect()
+}
+
+fn middle_score<T: Ord>(mut scores: Vec<T>) -> Result<T, ScoreError> {
+    if scores.is_empty() { return Err(ScoreError::NoIncompleteLines) }
     let index = scores.len() / 2;
-    scores.sort_by(|a, b| a.cmp(b));
-    scores[index]
+    scores.sort();
+    Ok(scores.swap_remove(index))
 }
 
-fn calculate_score(mut input: VecDeque<char>) -> usize {
-    let mut total_score = 0;
+/// Score of the closers completing `input`, or `None` if it overflows.
+fn calculate_score(mut input: Vec<usize>, brackets: &BracketSet) -> Option<u128> {
+    let mut total_score: u128 = 0;
 
-    while let Some(x) = input.pop_back()  {
-        total_score *= 5;
-        match x {
-            ')' => total_score += 1,
-            ']' => total_score += 2,
-            '}' => total_score += 3,
-            '>' => total_score += 4,
-            _   => (),
-        }
+    while let Some(x) = input.pop()  {
+        let score = brackets.pair(x).map_or(0, |p| p.completion_score);
+        total_score = total_score.checked_mul(5)?.checked_add(score as u128)?;
+    }
+
+    Some(total_score)
+}
+
+fn calculate_big_score(mut input: Vec<usize>, brackets: &BracketSet) -> BigUint {
+    let mut total_score = BigUint::zero();
+
+    while let Some(x) = input.pop()  {
+        let score = brackets.pair(x).map_or(0, |p| p.completion_score);
+        total_score = &total_score.mul_small(5) + &BigUint::from(score as u64);
     }
 
     total_score
 }
 
 pub fn calculate_corrupted_chars(data: Vec<Vec<char>>) -> usize {
+    calculate_corrupted_chars_with_brackets(data, &BracketSet::default())
+}
+
+pub fn calculate_corrupted_chars_with_brackets(data: Vec<Vec<char>>, brackets: &BracketSet) -> usize {
     data.into_iter()
-        .map(|chars| find_corrupted_char(&chars))
+        .map(|chars| find_corrupted_char(&chars, brackets))
         .map(|output| {
             match output {
                 None => 0,
-                Some(c) => char_to_score(c),
+                Some(pair) => brackets.pair(pair).map_or(0, |p| p.corruption_score),
             }
         })
         .fold(0, |acc, n| acc + n)
@@ -143,6 +154,7 @@ pub fn calculate_corrupted_chars(data: Vec<Vec<char>>) -> usize {
 mod tests {
     
     use crate::data;
+    use super::brackets::BracketPair;
     use super::*;
 
     #[test]
@@ -158,6 +170,35 @@ mod tests {
         let data: Vec<Row> = data::read_input_data("./data/d10/data.txt").expect("Invalid input data");
         let data: Vec<Vec<char>> = data.into_iter().map(|r| r.0).collect();
         let score = find_middle_completions_score(data);
-        assert_eq!(score, 3404870164);
+        assert_eq!(score, Ok(3404870164));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn check_custom_brackets() {
+        let brackets = BracketSet::new(vec![
+            BracketPair::new("«", "»", 10, 1),
+            BracketPair::new("/*", "*/", 100, 2),
+            BracketPair::new("(", ")", 1000, 3),
+        ]).expect("Invalid brackets");
+        let lines: Vec<Vec<char>> = ["«/*(x)*/»", "«(/*»", "/*«((", "(*/"].iter().map(|l| l.chars().collect()).collect();
+        assert_eq!(calculate_corrupted_chars_with_brackets(lines.clone(), &brackets), 110);
+        // "/*«((" is completed by "))»*/"; the complete line is not scored.
+        assert_eq!(find_middle_completions_score_with_brackets(lines, &brackets), Ok(((3 * 5 + 3) * 5 + 1) * 5 + 2));
+    }
+
+    #[test]
+    fn score_long_and_missing_completions() {
+        let line: Vec<char> = "<".repeat(60).chars().collect();
+        let expected = (0..60).fold(BigUint::zero(), |acc, _| &acc.mul_small(5) + &BigUint::from(4u64));
+        assert_eq!(find_middle_completions_score(vec![line.clone()]), Err(ScoreError::Overflow));
+        assert_eq!(find_middle_completions_big_score_with_brackets(vec![line], &BracketSet::default()), Ok(expected));
+
+        let short: Vec<char> = "<".repeat(40).chars().collect();
+        assert_eq!(find_middle_completions_score(vec![short]), Ok(5u128.pow(40) - 1));
+
+        let lines: Vec<Vec<char>> = ["(]", "<>"].iter().map(|l| l.chars().collect()).collect();
+        assert_eq!(find_middle_completions_score(Vec::new()), Err(ScoreError::NoIncompleteLines));
+        assert_eq!(find_middle_completions_score(lines[..1].to_vec()), Err(ScoreError::NoIncompleteLines));
+        assert_eq!(find_middle_completions_score(lines), Err(ScoreError::NoIncompleteLines));
+    }
+}
@@ -0,0 +1,137 @@
+/// An opening and a closing token with their scores: `corruption_score` when the closer shows up
+/// where another one was expected, `completion_score` when it has to be added to complete a line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketPair {
+    pub open: String,
+    pub close: String,
+    pub corruption_score: usize,
+    pub completion_score: usize,
+}
+
+impl BracketPair {
+
+    pub fn new(open: &str, close: &str, corruption_score: usize, completion_score: usize) -> Self {
+        Self { open: String::from(open), close: String::from(close), corruption_score, completion_score }
+    }
+}
+
+/// What a piece of a line stands for, brackets being referred to by their index in the set.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Token {
+    Open(usize),
+    Close(usize),
+    Other(char),
+}
+
+/// A token along with the characters it spans in the line, `end` excluded.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Lexeme {
+    pub token: Token,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The delimiters a line is checked against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketSet {
+    pairs: Vec<BracketPair>,
+    tokens: Vec<(Vec<char>, Token)>,
+}
+
+impl BracketSet {
+
+    /// Tokens may span several characters but must not be empty nor be used twice.
+    pub fn new(pairs: Vec<BracketPair>) -> Result<Self, String> {
+        if pairs.is_empty() { return Err(String::from("No bracket pairs")) }
+        let mut tokens: Vec<(Vec<char>, Token)> = Vec::new();
+        for (index, pair) in pairs.iter().enumerate() {
+            for (text, token) in [(&pair.open, Token::Open(index)), (&pair.close, Token::Close(index))] {
+                if text.is_empty() { return Err(format!("Empty token in pair {}", index + 1)) }
+                let text: Vec<char> = text.chars().collect();
+                if tokens.iter().any(|(other, _)| *other == text) {
+                    return Err(format!("Token '{}' used twice", text.iter().collect::<String>()));
+                }
+                tokens.push((text, token));
+            }
+        }
+        // Longest tokens first, so that the tokenizer picks the longest match.
+        tokens.sort_by_key(|(text, _)| std::cmp::Reverse(text.len()));
+        Ok(Self { pairs, tokens })
+    }
+
+    pub fn pairs(&self) -> &[BracketPair] {
+        &self.pairs
+    }
+
+    pub fn pair(&self, index: usize) -> Option<&BracketPair> {
+        self.pairs.get(index)
+    }
+
+    /// Longest token starting at `start`, if any.
+    pub fn token_at(&self, chars: &[char], start: usize) -> Option<(Token, usize)> {
+        let rest = chars.get(start..)?;
+        self.tokens.iter()
+            .find(|(text, _)| rest.starts_with(text))
+            .map(|(text, token)| (*token, text.len()))
+    }
+
+    /// Whether some token starts with `chars` and is longer, so that more input could change the match.
+    pub fn could_extend(&self, chars: &[char]) -> bool {
+        self.tokens.iter().any(|(text, _)| text.len() > chars.len() && text.starts_with(chars))
+    }
+
+    /// Splits a line into tokens, always taking the longest one; characters which start
+    /// no token come out one by one as `Token::Other`.
+    pub fn tokenize<'a>(&'a self, chars: &'a [char]) -> impl Iterator<Item = Lexeme> + 'a {
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            let &c = chars.get(start)?;
+            let (token, len) = self.token_at(chars, start).unwrap_or((Token::Other(c), 1));
+            let lexeme = Lexeme { token, start, end: start + len };
+            start += len;
+            Some(lexeme)
+        })
+    }
+}
+
+/// The puzzle's brackets: `()`, `[]`, `{}` and `<>`.
+impl Default for BracketSet {
+    fn default() -> Self {
+        let pairs = vec![
+            BracketPair::new("(", ")", 3, 1),
+            BracketPair::new("[", "]", 57, 2),
+            BracketPair::new("{", "}", 1197, 3),
+            BracketPair::new("<", ">", 25137, 4),
+        ];
+        Self::new(pairs).expect("Invalid default brackets")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn tokenize_longest_match() {
+        let set = BracketSet::new(vec![
+            BracketPair::new("/*", "*/", 1, 1),
+            BracketPair::new("(", ")", 2, 2),
+            BracketPair::new("*", "**", 3, 3),
+        ]).expect("Invalid brackets");
+        let chars: Vec<char> = "(/**/x***)".chars().collect();
+        let tokens: Vec<(Token, usize, usize)> = set.tokenize(&chars).map(|l| (l.token, l.start, l.end)).collect();
+        assert_eq!(tokens, vec![
+            (Token::Open(1), 0, 1), (Token::Open(0), 1, 3), (Token::Close(0), 3, 5), (Token::Other('x'), 5, 6),
+            (Token::Close(2), 6, 8), (Token::Open(2), 8, 9), (Token::Close(1), 9, 10),
+        ]);
+    }
+
+    #[test]
+    fn reject_invalid_sets() {
+        assert!(BracketSet::new(Vec::new()).is_err());
+        assert!(BracketSet::new(vec![BracketPair::new("", ")", 1, 1)]).is_err());
+        assert!(BracketSet::new(vec![BracketPair::new("|", "|", 1, 1)]).is_err());
+        assert!(BracketSet::new(vec![BracketPair::new("(", ")", 1, 1), BracketPair::new("[", ")", 1, 1)]).is_err());
+    }
+}
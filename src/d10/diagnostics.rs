@@ -0,0 +1,151 @@
+use std::fmt;
+
+use super::brackets::{BracketSet, Token};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DiagnosticKind {
+    /// A closer not matching the innermost open bracket, opened at column `opener`.
+    Mismatched { expected: String, found: String, opener: usize },
+    /// A closer with nothing open.
+    UnexpectedCloser { found: String },
+    /// A character which is not part of any bracket.
+    UnknownCharacter { found: char },
+}
+
+/// A problem found in a line. `line` and `column` start at 1, columns counting characters;
+/// `width` is the number of characters of the offending token.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub width: usize,
+    pub kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+
+    pub fn message(&self) -> String {
+        match &self.kind {
+            DiagnosticKind::Mismatched { expected, found, .. } => format!("expected `{}`, found `{}`", expected, found),
+            DiagnosticKind::UnexpectedCloser { found } => format!("unexpected `{}` with nothing open", found),
+            DiagnosticKind::UnknownCharacter { found } => format!("unknown character `{}`", found),
+        }
+    }
+
+    /// Writes the diagnostic the way compilers do, quoting `source` (the line it was found in)
+    /// with a caret under the offending token and a dash under the opener it does not match.
+    pub fn render(&self, source: &str) -> String {
+        let gutter = self.line.to_string();
+        let pad = " ".repeat(gutter.len());
+
+        // Columns start at 1; a column of 0 is drawn as the first one.
+        let column = self.column.saturating_sub(1);
+        let mut markers: Vec<char> = vec![' '; column + self.width];
+        if let DiagnosticKind::Mismatched { opener, .. } = self.kind {
+            if let Some(marker) = markers.get_mut(opener.saturating_sub(1)) { *marker = '-' }
+        }
+        markers[column..].iter_mut().for_each(|m| *m = '^');
+        let label = match &self.kind {
+            DiagnosticKind::Mismatched { expected, .. } => format!("expected `{}`", expected),
+            DiagnosticKind::UnexpectedCloser { .. } => String::from("no matching opener"),
+            DiagnosticKind::UnknownCharacter { .. } => String::from("not a bracket"),
+        };
+
+        format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {} {}\n",
+            self.message(), pad, self.line, self.column, pad, gutter, source, pad, markers.iter().collect::<String>(), label,
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message())
+    }
+}
+
+/// Checks one line, numbered `line`. Extra closers and unknown characters are reported and
+/// skipped; the first mismatched closer is reported last as what follows it cannot be trusted.
+pub fn check_line(line: usize, chars: &[char], brackets: &BracketSet) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut open: Vec<(usize, usize)> = Vec::new();
+
+    for lexeme in brackets.tokenize(chars) {
+        let column = lexeme.start + 1;
+        let width = lexeme.end - lexeme.start;
+        let kind = match lexeme.token {
+            Token::Open(pair) => { open.push((pair, column)); continue }
+            Token::Close(pair) => match open.pop() {
+                Some((expected, _)) if expected == pair => continue,
+                Some((expected, opener)) => DiagnosticKind::Mismatched {
+                    expected: brackets.pairs()[expected].close.clone(),
+                    found: brackets.pairs()[pair].close.clone(),
+                    opener,
+                },
+                None => DiagnosticKind::UnexpectedCloser { found: brackets.pairs()[pair].close.clone() },
+            },
+            Token::Other(found) => DiagnosticKind::UnknownCharacter { found },
+        };
+        let is_fatal = matches!(kind, DiagnosticKind::Mismatched { .. });
+        diagnostics.push(Diagnostic { line, column, width, kind });
+        if is_fatal { break }
+    }
+    diagnostics
+}
+
+/// Checks every line of `data`, numbering lines from 1.
+pub fn check(data: &str, brackets: &BracketSet) -> Vec<Diagnostic> {
+    data.lines()
+        .enumerate()
+        .flat_map(|(index, line)| check_line(index + 1, &line.chars().collect::<Vec<char>>(), brackets))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn report_mismatched_closer() {
+        let source = "{([(<{}[<>[]}>{[]{[(<()>";
+        let diagnostics = check(source, &BracketSet::default());
+        let expected = Diagnostic {
+            line: 1,
+            column: 13,
+            width: 1,
+            kind: DiagnosticKind::Mismatched { expected: String::from("]"), found: String::from("}"), opener: 8 },
+        };
+        assert_eq!(diagnostics, vec![expected]);
+        assert_eq!(diagnostics[0].to_string(), "1:13: expected `]`, found `}`");
+
+        let rendered = "\
+error: expected `]`, found `}`
+ --> 1:13
+  |
+1 | {([(<{}[<>[]}>{[]{[(<()>
+  |        -    ^ expected `]`
+";
+        assert_eq!(diagnostics[0].render(source), rendered);
+    }
+
+    #[test]
+    fn report_extra_closers_and_unknown_characters() {
+        let data = "[<>]\n()) x<]>";
+        let found: Vec<(usize, usize, DiagnosticKind)> = check(data, &BracketSet::default()).into_iter()
+            .map(|d| (d.line, d.column, d.kind))
+            .collect();
+        assert_eq!(found, vec![
+            (2, 3, DiagnosticKind::UnexpectedCloser { found: String::from(")") }),
+            (2, 4, DiagnosticKind::UnknownCharacter { found: ' ' }),
+            (2, 5, DiagnosticKind::UnknownCharacter { found: 'x' }),
+            (2, 7, DiagnosticKind::Mismatched { expected: String::from(">"), found: String::from("]"), opener: 6 }),
+        ]);
+
+        let diagnostic = check_line(12, &"(]".chars().collect::<Vec<char>>(), &BracketSet::default()).remove(0);
+        assert_eq!(diagnostic.render("(]"), "error: expected `)`, found `]`\n  --> 12:2\n   |\n12 | (]\n   | -^ expected `)`\n");
+
+        let zero = Diagnostic { column: 0, kind: DiagnosticKind::Mismatched { expected: String::from(")"), found: String::from("]"), opener: 0 }, ..diagnostic };
+        assert_eq!(zero.render("]"), "error: expected `)`, found `]`\n  --> 12:0\n   |\n12 | ]\n   | ^ expected `)`\n");
+    }
+}
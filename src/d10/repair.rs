@@ -0,0 +1,233 @@
+use super::analyze_chars;
+use super::brackets::{BracketSet, Lexeme, Token};
+
+/// A change to a line, `position` being a character offset in the original line.
+/// Insertions go before the character at `position`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Edit {
+    Insert { position: usize, text: String },
+    Delete { position: usize, text: String },
+    Substitute { position: usize, from: String, to: String },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Repair {
+    pub repaired: String,
+    pub edits: Vec<Edit>,
+    pub cost: usize,
+}
+
+/// How the first token of an interval is dealt with.
+#[derive(Debug, Clone, Copy)]
+enum Choice {
+    Empty,
+    /// The token is a closer and gets an opener inserted right before it.
+    InsertOpener,
+    /// The token is an opener closed by a closer inserted before token `k`.
+    InsertCloser(usize),
+    /// The token and token `k` become the opener and closer of the given pair.
+    Match(usize, usize),
+    Delete,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Op {
+    Keep,
+    Delete,
+    Replace(String),
+}
+
+struct Solver<'a> {
+    brackets: &'a BracketSet,
+    tokens: Vec<Token>,
+    cost: Vec<Vec<usize>>,
+    choice: Vec<Vec<Choice>>,
+}
+
+impl Solver<'_> {
+
+    /// Cheapest pair for tokens `i` and `k` to open and close, with the substitutions it takes.
+    fn match_cost(&self, i: usize, k: usize) -> (usize, usize) {
+        match (self.tokens[i], self.tokens[k]) {
+            (Token::Open(p), Token::Close(q)) if p == q => (p, 0),
+            (Token::Open(p), _) => (p, 1),
+            (_, Token::Close(q)) => (q, 1),
+            _ => (0, 2),
+        }
+    }
+
+    /// Fills `cost[i][j]`, the fewest edits balancing tokens `i..j`, for every interval.
+    /// Ties prefer inserting, then matching, then deleting.
+    fn solve(&mut self) {
+        let n = self.tokens.len();
+        for i in (0..n).rev() {
+            for j in i + 1..=n {
+                let mut best = (usize::MAX, Choice::Empty);
+                let mut consider = |cost: usize, choice: Choice| if cost < best.0 { best = (cost, choice) };
+
+                match self.tokens[i] {
+                    Token::Close(_) => consider(1 + self.cost[i + 1][j], Choice::InsertOpener),
+                    _ => for k in i + 1..=j {
+                        consider(1 + self.cost[i + 1][k] + self.cost[k][j], Choice::InsertCloser(k));
+                    },
+                }
+                for k in i + 1..j {
+                    let (pair, cost) = self.match_cost(i, k);
+                    consider(cost + self.cost[i + 1][k] + self.cost[k + 1][j], Choice::Match(k, pair));
+                }
+                consider(1 + self.cost[i + 1][j], Choice::Delete);
+
+                self.cost[i][j] = best.0;
+                self.choice[i][j] = best.1;
+            }
+        }
+    }
+
+    fn trace(&self, i: usize, j: usize, ops: &mut [Op], inserts: &mut Vec<(usize, String)>) {
+        if i == j { return }
+        match self.choice[i][j] {
+            Choice::Empty => (),
+            Choice::InsertOpener => {
+                if let Token::Close(pair) = self.tokens[i] { inserts.push((i, self.brackets.pairs()[pair].open.clone())) }
+                self.trace(i + 1, j, ops, inserts);
+            }
+            Choice::InsertCloser(k) => {
+                if let Token::Open(pair) = self.tokens[i] {
+                    self.trace(i + 1, k, ops, inserts);
+                    inserts.push((k, self.brackets.pairs()[pair].close.clone()));
+                }
+                self.trace(k, j, ops, inserts);
+            }
+            Choice::Match(k, pair) => {
+                let texts = &self.brackets.pairs()[pair];
+                if self.tokens[i] != Token::Open(pair) { ops[i] = Op::Replace(texts.open.clone()) }
+                if self.tokens[k] != Token::Close(pair) { ops[k] = Op::Replace(texts.close.clone()) }
+                self.trace(i + 1, k, ops, inserts);
+                self.trace(k + 1, j, ops, inserts);
+            }
+            Choice::Delete => {
+                ops[i] = Op::Delete;
+                self.trace(i + 1, j, ops, inserts);
+            }
+        }
+    }
+}
+
+/// Finds the fewest insertions, deletions and substitutions of brackets balancing the line,
+/// by dynamic programming over the intervals of its bracket tokens in O(n^3). Other characters
+/// are kept as they are. Every edit costs 1; among equally cheap repairs, insertions are preferred.
+pub fn repair_line(chars: &[char], brackets: &BracketSet) -> Repair {
+    if is_balanced(chars, brackets) {
+        return Repair { repaired: chars.iter().collect(), edits: Vec::new(), cost: 0 };
+    }
+
+    let lexemes: Vec<Lexeme> = brackets.tokenize(chars).collect();
+    let bracket_lexemes: Vec<&Lexeme> = lexemes.iter().filter(|l| !matches!(l.token, Token::Other(_))).collect();
+    let n = bracket_lexemes.len();
+    let mut solver = Solver {
+        brackets,
+        tokens: bracket_lexemes.iter().map(|l| l.token).collect(),
+        cost: vec![vec![0; n + 1]; n + 1],
+        choice: vec![vec![Choice::Empty; n + 1]; n + 1],
+    };
+    solver.solve();
+
+    let mut ops = vec![Op::Keep; n];
+    let mut inserts: Vec<(usize, String)> = Vec::new();
+    solver.trace(0, n, &mut ops, &mut inserts);
+    inserts.sort_by_key(|&(index, _)| index);
+
+    let text = |lexeme: &Lexeme| -> String { chars[lexeme.start..lexeme.end].iter().collect() };
+    let mut repaired = String::new();
+    let mut edits = Vec::new();
+    let mut pending = inserts.into_iter().peekable();
+    let mut index = 0;
+    for lexeme in &lexemes {
+        if matches!(lexeme.token, Token::Other(_)) {
+            repaired.push_str(&text(lexeme));
+            continue;
+        }
+        while let Some((_, insert)) = pending.next_if(|&(at, _)| at == index) {
+            repaired.push_str(&insert);
+            edits.push(Edit::Insert { position: lexeme.start, text: insert });
+        }
+        match &ops[index] {
+            Op::Keep => repaired.push_str(&text(lexeme)),
+            Op::Delete => edits.push(Edit::Delete { position: lexeme.start, text: text(lexeme) }),
+            Op::Replace(to) => {
+                repaired.push_str(to);
+                edits.push(Edit::Substitute { position: lexeme.start, from: text(lexeme), to: to.clone() });
+            }
+        }
+        index += 1;
+    }
+    for (_, insert) in pending {
+        repaired.push_str(&insert);
+        edits.push(Edit::Insert { position: chars.len(), text: insert });
+    }
+
+    Repair { repaired, edits, cost: solver.cost[0][n] }
+}
+
+/// Whether every closer matches the last opened bracket and nothing is left open.
+pub fn is_balanced(chars: &[char], brackets: &BracketSet) -> bool {
+    analyze_chars(chars, brackets).is_ok_and(|open| open.is_balanced())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::d10::Row;
+    use crate::data;
+    use super::*;
+
+    fn repair(line: &str) -> Repair {
+        repair_line(&line.chars().collect::<Vec<char>>(), &BracketSet::default())
+    }
+
+    #[test]
+    fn repair_small_lines() {
+        assert_eq!(repair("(]"), Repair {
+            repaired: String::from("()"),
+            edits: vec![Edit::Substitute { position: 1, from: String::from("]"), to: String::from(")") }],
+            cost: 1,
+        });
+        assert_eq!(repair("())"), Repair {
+            repaired: String::from("()()"),
+            edits: vec![Edit::Insert { position: 2, text: String::from("(") }],
+            cost: 1,
+        });
+        assert_eq!(repair("<{"), Repair {
+            repaired: String::from("<>"),
+            edits: vec![Edit::Substitute { position: 1, from: String::from("{"), to: String::from(">") }],
+            cost: 1,
+        });
+        assert_eq!(repair("a(b]c{}").repaired, "a(b)c{}");
+        assert_eq!(repair("[<>({}){}[([])<>]]").cost, 0);
+        assert_eq!(repair("").cost, 0);
+        assert_eq!(repair("((((").edits.len(), 2);
+        assert_eq!(repair(")(").cost, 2);
+    }
+
+    #[test]
+    fn check_balance() {
+        let balanced = |line: &str| is_balanced(&line.chars().collect::<Vec<char>>(), &BracketSet::default());
+        assert!(balanced("a(b)[<>]"));
+        assert!(!balanced("(]"));
+        assert!(!balanced("(()"));
+        assert!(!balanced("())"));
+        assert!(!balanced(")("));
+    }
+
+    #[test]
+    fn repaired_lines_are_balanced() {
+        let brackets = BracketSet::default();
+        let data: Vec<Row> = data::read_input_data("./data/d10/data.txt").expect("Invalid input data");
+        for row in data.into_iter().take(10) {
+            let repair = repair_line(&row.0, &brackets);
+            assert!(is_balanced(&repair.repaired.chars().collect::<Vec<char>>(), &brackets));
+            assert_eq!(repair.edits.len(), repair.cost);
+            assert!(repair.cost > 0);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::io::{Error, Read};
+
+use super::brackets::{BracketSet, Token};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StreamStatus {
+    Ok,
+    /// The closer starting at byte `offset` does not match the last opener.
+    Corrupted { offset: u64 },
+    Incomplete { open: usize },
+    /// The closer starting at byte `offset` was found with nothing open. Such closers are skipped,
+    /// as in scoring, and `open` brackets were left open.
+    Unopened { offset: u64, open: usize },
+}
+
+/// Checks brackets of input fed in chunks of bytes, keeping only the stack of open brackets,
+/// so lines of any length can be checked. Chunks may split characters and multi-character
+/// tokens anywhere. Invalid UTF-8 sequences are read as replacement characters.
+pub struct StreamChecker<'a> {
+    brackets: &'a BracketSet,
+    reset_at_newlines: bool,
+    open: Vec<usize>,
+    corrupted: Option<u64>,
+    unopened: Option<u64>,
+    /// Bytes of a character split by the end of the last chunk.
+    partial: Vec<u8>,
+    /// Characters which could still be the start of a longer token, with their byte offsets.
+    pending: Vec<(char, u64)>,
+    offset: u64,
+    line_start: u64,
+    finished: Vec<StreamStatus>,
+}
+
+impl<'a> StreamChecker<'a> {
+
+    /// With `reset_at_newlines`, every line is checked on its own and offsets count from its start.
+    pub fn new(brackets: &'a BracketSet, reset_at_newlines: bool) -> Self {
+        Self {
+            brackets,
+            reset_at_newlines,
+            open: Vec::new(),
+            corrupted: None,
+            unopened: None,
+            partial: Vec::new(),
+            pending: Vec::new(),
+            offset: 0,
+            line_start: 0,
+            finished: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.partial.extend_from_slice(bytes);
+        let data = std::mem::take(&mut self.partial);
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            let (valid, invalid) = match std::str::from_utf8(rest) {
+                Ok(text) => (text, None),
+                Err(e) => (std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or(""), Some(e)),
+            };
+            for c in valid.chars() {
+                self.push_char(c, c.len_utf8() as u64);
+            }
+            rest = &rest[valid.len()..];
+            match invalid.map(|e| e.error_len()) {
+                None => break,
+                Some(Some(len)) => {
+                    self.push_char(char::REPLACEMENT_CHARACTER, len as u64);
+                    rest = &rest[len..];
+                }
+                Some(None) => {
+                    self.partial = rest.to_vec();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn push_char(&mut self, c: char, len: u64) {
+        self.pending.push((c, self.offset));
+        self.offset += len;
+        self.consume(false);
+    }
+
+    /// Turns pending characters into tokens, holding back those which may still grow into a longer one.
+    fn consume(&mut self, flush: bool) {
+        let chars: Vec<char> = self.pending.iter().map(|&(c, _)| c).collect();
+        let mut start = 0;
+        while start < chars.len() {
+            if !flush && self.brackets.could_extend(&chars[start..]) { break }
+            let (token, len) = self.brackets.token_at(&chars, start).unwrap_or((Token::Other(chars[start]), 1));
+            self.apply(token, self.pending[start].1);
+            start += len;
+        }
+        self.pending.drain(..start);
+    }
+
+    fn apply(&mut self, token: Token, offset: u64) {
+        if token == Token::Other('\n') && self.reset_at_newlines {
+            let status = self.line_status();
+            self.finished.push(status);
+            self.open.clear();
+            self.corrupted = None;
+            self.unopened = None;
+            self.line_start = offset + 1;
+            return;
+        }
+        if self.corrupted.is_some() { return }
+        match token {
+            Token::Open(pair) => self.open.push(pair),
+            Token::Close(pair) => match self.open.pop() {
+                Some(last) if last == pair => (),
+                Some(_) => self.corrupted = Some(offset - self.line_start),
+                None => { self.unopened.get_or_insert(offset - self.line_start); }
+            },
+            Token::Other(_) => (),
+        }
+    }
+
+    fn line_status(&self) -> StreamStatus {
+        match (self.corrupted, self.unopened) {
+            (Some(offset), _) => StreamStatus::Corrupted { offset },
+            (None, Some(offset)) => StreamStatus::Unopened { offset, open: self.open.len() },
+            (None, None) if self.open.is_empty() => StreamStatus::Ok,
+            (None, None) => StreamStatus::Incomplete { open: self.open.len() },
+        }
+    }
+
+    /// Status of the input read so far (of the current line when resetting at newlines). The last
+    /// few characters may be held back while they could start a multi-character token.
+    pub fn status(&self) -> StreamStatus {
+        self.line_status()
+    }
+
+    /// Ends the input, settling anything held back, and returns the final status.
+    pub fn finish(&mut self) -> StreamStatus {
+        if !self.partial.is_empty() {
+            self.partial.clear();
+            self.push_char(char::REPLACEMENT_CHARACTER, 1);
+        }
+        self.consume(true);
+        self.line_status()
+    }
+
+    /// Statuses of the lines ended since the last call, when resetting at newlines.
+    pub fn take_finished(&mut self) -> Vec<StreamStatus> {
+        std::mem::take(&mut self.finished)
+    }
+}
+
+/// Checks every line read from `reader` without holding any of them in memory.
+pub fn check_lines<R: Read>(mut reader: R, brackets: &BracketSet) -> Result<Vec<StreamStatus>, Error> {
+    let mut checker = StreamChecker::new(brackets, true);
+    let mut statuses = Vec::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 { break }
+        checker.feed(&buffer[..read]);
+        statuses.append(&mut checker.take_finished());
+    }
+    let last = checker.finish();
+    if checker.offset > checker.line_start { statuses.push(last) }
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::d10::brackets::BracketPair;
+    use crate::d10::{analyze_chars, Row};
+    use crate::data;
+    use super::*;
+
+    #[test]
+    fn check_byte_by_byte() {
+        let brackets = BracketSet::default();
+        let mut checker = StreamChecker::new(&brackets, false);
+        for &byte in b"{([(<{}[<>[]" { checker.feed(&[byte]) }
+        assert_eq!(checker.status(), StreamStatus::Incomplete { open: 6 });
+        for &byte in b"}>{[]{[(<()>" { checker.feed(&[byte]) }
+        assert_eq!(checker.finish(), StreamStatus::Corrupted { offset: 12 });
+    }
+
+    #[test]
+    fn check_split_tokens_and_characters() {
+        let brackets = BracketSet::new(vec![
+            BracketPair::new("\u{ab}", "\u{bb}", 1, 1),
+            BracketPair::new("/*", "*/", 2, 2),
+            BracketPair::new("/", "\\", 3, 3),
+        ]).expect("Invalid brackets");
+        let bytes = "\u{ab}/*/\\*/\u{bb}".as_bytes();
+        for split in 0..=bytes.len() {
+            let mut checker = StreamChecker::new(&brackets, false);
+            checker.feed(&bytes[..split]);
+            checker.feed(&bytes[split..]);
+            assert_eq!(checker.finish(), StreamStatus::Ok);
+        }
+
+        let mut checker = StreamChecker::new(&brackets, false);
+        checker.feed("\u{ab}/".as_bytes());
+        assert_eq!(checker.status(), StreamStatus::Incomplete { open: 1 });
+        assert_eq!(checker.finish(), StreamStatus::Incomplete { open: 2 });
+
+        let mut checker = StreamChecker::new(&brackets, false);
+        checker.feed(&[b'/', 0xff, 0xc2]);
+        assert_eq!(checker.finish(), StreamStatus::Incomplete { open: 1 });
+    }
+
+    #[test]
+    fn check_lines_separately() {
+        let statuses = check_lines("()\n(]\n\n((\n())((\n)(]".as_bytes(), &BracketSet::default()).expect("Unable to read");
+        assert_eq!(statuses, vec![
+            StreamStatus::Ok,
+            StreamStatus::Corrupted { offset: 1 },
+            StreamStatus::Ok,
+            StreamStatus::Incomplete { open: 2 },
+            StreamStatus::Unopened { offset: 2, open: 2 },
+            StreamStatus::Corrupted { offset: 2 },
+        ]);
+        let stray: Vec<char> = "())((".chars().collect();
+        let open = analyze_chars(&stray, &BracketSet::default()).expect("Corrupted line");
+        assert_eq!((open.pairs.len(), open.unopened), (2, 1));
+
+        let file = std::fs::File::open("./data/d10/data.txt").expect("Invalid input data");
+        let statuses = check_lines(file, &BracketSet::default()).expect("Unable to read");
+        let data: Vec<Row> = data::read_input_data("./data/d10/data.txt").expect("Invalid input data");
+        assert_eq!(statuses.len(), data.len());
+        for (status, row) in statuses.into_iter().zip(data) {
+            match analyze_chars(&row.0, &BracketSet::default()) {
+                Ok(open) if open.unopened > 0 => assert!(matches!(status, StreamStatus::Unopened { open: n, .. } if n == open.pairs.len())),
+                Ok(open) if open.pairs.is_empty() => assert_eq!(status, StreamStatus::Ok),
+                Ok(open) => assert_eq!(status, StreamStatus::Incomplete { open: open.pairs.len() }),
+                Err(_) => assert!(matches!(status, StreamStatus::Corrupted { .. })),
+            }
+        }
+    }
+}
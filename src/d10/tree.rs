@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use super::brackets::{BracketSet, Token};
+use super::Row;
+
+/// A bracketed chunk of a line. `kind` is the index of its pair in the bracket set and `span`
+/// the characters it covers; a chunk left open spans until the end of the line or the error.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Chunk {
+    pub kind: usize,
+    pub span: Range<usize>,
+    pub children: Vec<Chunk>,
+    pub closed: bool,
+}
+
+impl Chunk {
+
+    fn depth(&self) -> usize {
+        1 + self.children.iter().map(|c| c.depth()).max().unwrap_or(0)
+    }
+
+    fn count(&self, counts: &mut [usize]) {
+        counts[self.kind] += 1;
+        self.children.iter().for_each(|c| c.count(counts));
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParseStatus {
+    Complete,
+    /// `open` chunks were still open at the end of the line.
+    Incomplete { open: usize },
+    /// The closer at character `position` does not match the last open chunk.
+    Corrupted { position: usize },
+    /// The closer at character `position` was found with nothing open. Such closers are skipped,
+    /// as in scoring, and `open` chunks were left open.
+    Unopened { position: usize, open: usize },
+}
+
+/// Chunks of a line, parsed up to its end or its first error.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChunkTree {
+    pub chunks: Vec<Chunk>,
+    pub status: ParseStatus,
+    kinds: usize,
+}
+
+impl ChunkTree {
+
+    /// Characters other than brackets are skipped.
+    pub fn parse(chars: &[char], brackets: &BracketSet) -> Self {
+        let mut chunks: Vec<Chunk> = Vec::new();
+        let mut open: Vec<Chunk> = Vec::new();
+        let mut status = ParseStatus::Complete;
+        let mut unopened = None;
+        let mut end = chars.len();
+
+        for lexeme in brackets.tokenize(chars) {
+            match lexeme.token {
+                Token::Open(kind) => open.push(Chunk { kind, span: lexeme.start..lexeme.start, children: Vec::new(), closed: false }),
+                Token::Close(kind) if open.last().map(|c| c.kind) == Some(kind) => {
+                    let mut chunk = open.pop().expect("Chunk to close");
+                    chunk.span.end = lexeme.end;
+                    chunk.closed = true;
+                    open.last_mut().map_or(&mut chunks, |parent| &mut parent.children).push(chunk);
+                }
+                Token::Close(_) if open.is_empty() => { unopened.get_or_insert(lexeme.start); }
+                Token::Close(_) => {
+                    status = ParseStatus::Corrupted { position: lexeme.start };
+                    end = lexeme.start;
+                    break;
+                }
+                Token::Other(_) => (),
+            }
+        }
+
+        if status == ParseStatus::Complete {
+            if let Some(position) = unopened {
+                status = ParseStatus::Unopened { position, open: open.len() };
+            } else if !open.is_empty() {
+                status = ParseStatus::Incomplete { open: open.len() };
+            }
+        }
+        while let Some(mut chunk) = open.pop() {
+            chunk.span.end = end;
+            open.last_mut().map_or(&mut chunks, |parent| &mut parent.children).push(chunk);
+        }
+
+        Self { chunks, status, kinds: brackets.pairs().len() }
+    }
+
+    /// Deepest nesting of chunks, 0 for a line without any.
+    pub fn max_depth(&self) -> usize {
+        self.chunks.iter().map(|c| c.depth()).max().unwrap_or(0)
+    }
+
+    /// Number of chunks of each kind, open ones included.
+    pub fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.kinds];
+        self.chunks.iter().for_each(|c| c.count(&mut counts));
+        counts
+    }
+
+    /// Length of the longest prefix made of closed chunks only.
+    pub fn longest_valid_prefix(&self) -> usize {
+        let limit = match self.status {
+            ParseStatus::Unopened { position, .. } => position,
+            _ => usize::MAX,
+        };
+        self.chunks.iter().take_while(|c| c.closed && c.span.end <= limit).last().map_or(0, |c| c.span.end)
+    }
+}
+
+impl Row {
+
+    pub fn chunk_tree(&self, brackets: &BracketSet) -> ChunkTree {
+        ChunkTree::parse(&self.0, brackets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(line: &str) -> ChunkTree {
+        let row: Row = line.parse().expect("Invalid row");
+        row.chunk_tree(&BracketSet::default())
+    }
+
+    fn chunk(kind: usize, span: Range<usize>, closed: bool, children: Vec<Chunk>) -> Chunk {
+        Chunk { kind, span, children, closed }
+    }
+
+    #[test]
+    fn parse_incomplete_line() {
+        let tree = parse("([]<{}>)[");
+        assert_eq!(tree.chunks, vec![
+            chunk(0, 0..8, true, vec![
+                chunk(1, 1..3, true, vec![]),
+                chunk(3, 3..7, true, vec![chunk(2, 4..6, true, vec![])]),
+            ]),
+            chunk(1, 8..9, false, vec![]),
+        ]);
+        assert_eq!(tree.status, ParseStatus::Incomplete { open: 1 });
+        assert_eq!(tree.max_depth(), 3);
+        assert_eq!(tree.counts(), vec![1, 2, 1, 1]);
+        assert_eq!(tree.longest_valid_prefix(), 8);
+    }
+
+    #[test]
+    fn parse_corrupted_and_complete_lines() {
+        let tree = parse("(<[>)");
+        assert_eq!(tree.status, ParseStatus::Corrupted { position: 3 });
+        assert_eq!(tree.chunks, vec![chunk(0, 0..3, false, vec![chunk(3, 1..3, false, vec![chunk(1, 2..3, false, vec![])])])]);
+        assert_eq!(tree.longest_valid_prefix(), 0);
+
+        let tree = parse("())((");
+        assert_eq!(tree.status, ParseStatus::Unopened { position: 2, open: 2 });
+        assert_eq!(tree.chunks.len(), 2);
+        assert_eq!(tree.longest_valid_prefix(), 2);
+        assert_eq!(parse("(){}][]").longest_valid_prefix(), 4);
+        assert_eq!(parse(")(]").status, ParseStatus::Corrupted { position: 2 });
+
+        let tree = parse("[<>({}){}[([])<>]]");
+        assert_eq!(tree.status, ParseStatus::Complete);
+        assert_eq!(tree.max_depth(), 4);
+        assert_eq!(tree.longest_valid_prefix(), 18);
+        assert_eq!(parse("").max_depth(), 0);
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// Cell of a grid, counted from the top left corner.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    pub fn new(row: usize, col: usize) -> Self { Self { row, col } }
+}
+
+/// Which cells count as neighbors: the four sharing a side, or the eight sharing a side or a corner.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+const OFFSETS_4: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const OFFSETS_8: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+/// Rectangular grid stored row by row in a single vector.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self { width, height, cells: vec![value; width * height] }
+    }
+}
+
+impl<T> Grid<T> {
+
+    /// Every row must have the same length; no rows at all gives an empty grid.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, String> {
+        let width = rows.first().map_or(0, |row| row.len());
+        if let Some(index) = rows.iter().position(|row| row.len() != width) {
+            return Err(format!("Row {} has {} cells, expected {}", index + 1, rows[index].len(), width));
+        }
+        let height = rows.len();
+        Ok(Self { width, height, cells: rows.into_iter().flatten().collect() })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.row < self.height && pos.col < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.contains(pos) { self.cells.get(pos.row * self.width + pos.col) } else { None }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) { self.cells.get_mut(pos.row * self.width + pos.col) } else { None }
+    }
+
+    pub fn row(&self, index: usize) -> Option<&[T]> {
+        if index < self.height { Some(&self.cells[index * self.width..(index + 1) * self.width]) } else { None }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(move |index| &self.cells[index * self.width..(index + 1) * self.width])
+    }
+
+    pub fn column(&self, index: usize) -> Option<impl Iterator<Item = &T>> {
+        if index < self.width { Some(self.cells.iter().skip(index).step_by(self.width)) } else { None }
+    }
+
+    /// Every position in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| Pos::new(row, col)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Pos, &mut T)> {
+        self.positions().zip(self.cells.iter_mut())
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid { width: self.width, height: self.height, cells: self.cells.iter().map(f).collect() }
+    }
+
+    /// Neighbors inside the grid, in row-major order.
+    pub fn neighbors(&self, pos: Pos, connectivity: Connectivity) -> impl Iterator<Item = Pos> + '_ {
+        let offsets: &'static [(isize, isize)] = match connectivity {
+            Connectivity::Four => &OFFSETS_4,
+            Connectivity::Eight => &OFFSETS_8,
+        };
+        offsets.iter().filter_map(move |&(dr, dc)| {
+            let next = Pos::new(pos.row.checked_add_signed(dr)?, pos.col.checked_add_signed(dc)?);
+            if self.contains(next) { Some(next) } else { None }
+        })
+    }
+
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbors(pos, Connectivity::Four)
+    }
+
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbors(pos, Connectivity::Eight)
+    }
+}
+
+impl Grid<u32> {
+
+    /// Parses lines of decimal digits, one cell per digit.
+    pub fn parse_digits(data: &str) -> Result<Self, String> {
+        let rows = data.lines()
+            .map(|line| line.chars().map(|c| c.to_digit(10).ok_or(format!("Invalid digit '{}'", c))).collect())
+            .collect::<Result<Vec<Vec<u32>>, String>>()?;
+        Self::from_rows(rows)
+    }
+}
+
+impl Grid<char> {
+
+    /// Parses lines of characters, one cell per character.
+    pub fn parse_chars(data: &str) -> Result<Self, String> {
+        Self::from_rows(data.lines().map(|line| line.chars().collect()).collect())
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &Self::Output {
+        self.get(pos).expect("Position outside of the grid")
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut Self::Output {
+        self.get_mut(pos).expect("Position outside of the grid")
+    }
+}
+
+/// Writes every row on its own line, cells side by side.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn build_and_index_grids() {
+        let grid = Grid::parse_digits("123\n456").expect("Invalid grid");
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[Pos::new(1, 2)], 6);
+        assert_eq!(grid.get(Pos::new(2, 0)), None);
+        assert_eq!(grid.row(1), Some(&[4, 5, 6][..]));
+        assert_eq!(grid.column(1).map(|c| c.copied().collect::<Vec<u32>>()), Some(vec![2, 5]));
+        assert_eq!(grid.to_string(), "123\n456\n");
+
+        assert!(Grid::from_rows(vec![vec![1, 2], vec![3]]).is_err());
+        assert!(Grid::parse_digits("12\n3a").is_err());
+        assert!(Grid::<u32>::from_rows(Vec::new()).expect("Invalid grid").is_empty());
+        assert_eq!(Grid::parse_chars("#.\n.#").map(|g| g.map(|&c| c == '#').to_string()), Ok(String::from("truefalse\nfalsetrue\n")));
+    }
+
+    #[test]
+    fn find_neighbors() {
+        let grid = Grid::new(3, 3, 0);
+        let corner: Vec<Pos> = grid.neighbors4(Pos::new(0, 0)).collect();
+        assert_eq!(corner, vec![Pos::new(0, 1), Pos::new(1, 0)]);
+        assert_eq!(grid.neighbors4(Pos::new(1, 1)).count(), 4);
+        assert_eq!(grid.neighbors8(Pos::new(1, 1)).count(), 8);
+        assert_eq!(grid.neighbors8(Pos::new(2, 1)).collect::<Vec<Pos>>(), vec![
+            Pos::new(1, 0), Pos::new(1, 1), Pos::new(1, 2), Pos::new(2, 0), Pos::new(2, 2),
+        ]);
+    }
+}
@@ -1,4 +1,6 @@
+pub mod bigint;
 pub mod data;
+pub mod grid;
 pub mod d01;
 pub mod d02;
 pub mod d03;